@@ -0,0 +1,97 @@
+use std::fmt;
+
+use self::super::formatter::Options;
+
+pub const MAX_ALIAS_DEPTH : usize = 16;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum AliasError {
+    Cycle(Vec<String>),
+    TooDeep(Vec<String>),
+}
+
+impl fmt::Display for AliasError {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AliasError::Cycle(ref chain) => write!(f, "alias cycle: {}", chain.join(" -> ")),
+            AliasError::TooDeep(ref chain) => write!(f, "alias chain longer than {}: {} -> ...", MAX_ALIAS_DEPTH, chain.join(" -> ")),
+        }
+    }
+}
+
+// NOTE: Follows aliases until a name with an icon (or without a further alias), so `tsx: jsx` works even when `jsx` is itself an alias.
+pub fn resolve_alias(name : &str, aliases : &Options, icons : &Options) -> Result<String, AliasError> {
+    let mut chain = vec![name.to_string()];
+    let mut current = name;
+    while let Some(next) = aliases.get(current) {
+        if chain.contains(next) {
+            chain.push(next.clone());
+            return Err(AliasError::Cycle(chain))
+        }
+        if chain.len() > MAX_ALIAS_DEPTH {
+            return Err(AliasError::TooDeep(chain))
+        }
+        chain.push(next.clone());
+        current = next;
+        if icons.contains_key(current) {
+            break
+        }
+    }
+    Ok(current.to_string())
+}
+
+pub fn resolve_aliases(aliases : &Options, icons : &Options) -> Result<Options, AliasError> {
+    let mut names : Vec<&String> = aliases.keys().collect();
+    names.sort();
+    names.into_iter().map(|name| resolve_alias(name, aliases, icons).map(|target| (name.clone(), target))).collect()
+}
+
+#[cfg(test)]
+mod resolve_aliases_tests {
+    use super::*;
+
+    fn options(pairs : &[(&str, &str)]) -> Options {
+        pairs.iter().map(|&(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    #[test]
+    fn for_simple_case() {
+        let icons = options(&[("js", "J")]);
+        assert_eq!(Ok(options(&[("mjs", "js")])), resolve_aliases(&options(&[("mjs", "js")]), &icons))
+    }
+
+    #[test]
+    fn for_chains() {
+        let icons = options(&[("js", "J")]);
+        let aliases = options(&[("tsx", "jsx"), ("jsx", "ts"), ("ts", "js")]);
+        assert_eq!(Ok(options(&[("tsx", "js"), ("jsx", "js"), ("ts", "js")])), resolve_aliases(&aliases, &icons))
+    }
+
+    #[test]
+    fn when_target_has_its_own_icon() {
+        let icons = options(&[("js", "J"), ("jsx", "X")]);
+        let aliases = options(&[("tsx", "jsx"), ("jsx", "js")]);
+        assert_eq!(Ok("jsx".to_string()), resolve_alias("tsx", &aliases, &icons))
+    }
+
+    #[test]
+    fn when_target_is_unknown() {
+        assert_eq!(Ok("nope".to_string()), resolve_alias("tsx", &options(&[("tsx", "nope")]), &Options::new()))
+    }
+
+    #[test]
+    fn when_aliases_form_a_cycle() {
+        let aliases = options(&[("a", "b"), ("b", "c"), ("c", "a")]);
+        assert_eq!(Err(AliasError::Cycle(vec!["a".to_string(), "b".to_string(), "c".to_string(), "a".to_string()])), resolve_alias("a", &aliases, &Options::new()));
+        assert!(resolve_aliases(&aliases, &Options::new()).is_err())
+    }
+
+    #[test]
+    fn when_chain_is_too_deep() {
+        let aliases : Options = (0..MAX_ALIAS_DEPTH + 1).map(|i| (format!("a{}", i), format!("a{}", i + 1))).collect();
+        match resolve_alias("a0", &aliases, &Options::new()) {
+            Err(AliasError::TooDeep(chain)) => assert_eq!(MAX_ALIAS_DEPTH + 1, chain.len()),
+            other => panic!("{:?}", other),
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use self::super::colors::ColorType;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Span {
+    Text(String),
+    Colored(ColorType, String),
+    Link { color: ColorType, text: String, url: String },
+    Icon { glyph: String, width: usize },
+    Padding(usize),
+}
+
+impl Span {
+    // NOTE: Icons carry the width of their theme, emoji take two cells but are a single grapheme.
+    pub fn width(&self) -> usize {
+        match *self {
+            Span::Text(ref text) | Span::Colored(_, ref text) | Span::Link { ref text, .. } => text.graphemes(true).count(),
+            Span::Icon { width, .. } => width,
+            Span::Padding(width) => width,
+        }
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Cell {
+    pub spans: Vec<Span>,
+}
+
+impl Cell {
+    pub fn new() -> Cell {
+        Cell::default()
+    }
+
+    pub fn push(&mut self, span : Span) {
+        self.spans.push(span);
+    }
+
+    pub fn text(&mut self, text : String) {
+        self.push(Span::Text(text));
+    }
+
+    pub fn colored(&mut self, color : ColorType, text : String) {
+        self.push(Span::Colored(color, text));
+    }
+
+    pub fn width(&self) -> usize {
+        self.spans.iter().map(|span| span.width()).sum()
+    }
+
+    pub fn pad_to(&mut self, width : usize) {
+        let padding = width.saturating_sub(self.width());
+        if padding > 0 {
+            self.push(Span::Padding(padding));
+        }
+    }
+}
+
+#[cfg(test)]
+mod cell_tests {
+    use super::*;
+
+    #[test]
+    fn for_simple_case() {
+        let mut cell = Cell::new();
+        cell.text("12 ".to_string());
+        cell.colored(ColorType::Dir, "src".to_string());
+        assert_eq!(6, cell.width())
+    }
+
+    #[test]
+    fn when_cell_has_link_and_icon() {
+        let mut cell = Cell::new();
+        cell.push(Span::Icon { glyph: "\u{1f4c1}".to_string(), width: 2 });
+        cell.push(Span::Link { color: ColorType::Dir, text: "src".to_string(), url: "file:///home/src".to_string() });
+        assert_eq!(5, cell.width())
+    }
+
+    #[test]
+    fn when_padded() {
+        let mut cell = Cell::new();
+        cell.colored(ColorType::Dir, "src".to_string());
+        cell.pad_to(8);
+        assert_eq!(vec![Span::Colored(ColorType::Dir, "src".to_string()), Span::Padding(5)], cell.spans);
+        cell.pad_to(2);
+        assert_eq!(8, cell.width())
+    }
+}
@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use self::super::colors::{ColorType, RealColor};
+use self::super::icons::IconTheme;
+use self::super::formatter::Options;
+use self::super::aliases::resolve_alias;
+
+use serde_yaml;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ConfigFile {
+    pub name: String,
+    pub contents: String,
+}
+
+impl ConfigFile {
+    pub fn new(name : &str, contents : &str) -> ConfigFile {
+        ConfigFile { name: name.to_string(), contents: contents.to_string() }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ConfigFiles {
+    pub files: ConfigFile,
+    pub file_aliases: ConfigFile,
+    pub folders: ConfigFile,
+    pub folder_aliases: ConfigFile,
+    pub colors: ConfigFile,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Problem {
+    pub file: String,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}: {}", self.file, line, self.message),
+            None => write!(f, "{}: {}", self.file, self.message),
+        }
+    }
+}
+
+struct Pair {
+    line: Option<usize>,
+    key: String,
+    value: String,
+}
+
+fn unquote(text : &str) -> &str {
+    for quote in &["\"", "'"] {
+        if text.len() >= 2 && text.starts_with(quote) && text.ends_with(quote) {
+            return &text[1..text.len() - 1]
+        }
+    }
+    text
+}
+
+// NOTE: Values come from the same YAML parser used at runtime, the line scan only adds line numbers and duplicate keys (which the parser silently merges).
+fn pairs(file : &ConfigFile, problems : &mut Vec<Problem>) -> Vec<Pair> {
+    let mut values : Options = if file.contents.trim().is_empty() {
+        Options::new()
+    } else {
+        match serde_yaml::from_str(&file.contents) {
+            Ok(values) => values,
+            Err(e) => {
+                problems.push(Problem { file: file.name.clone(), line: None, message: e.to_string() });
+                return vec![]
+            },
+        }
+    };
+    let mut out : Vec<Pair> = vec![];
+    let mut seen : HashMap<String, usize> = HashMap::new();
+    for (i, raw) in file.contents.lines().enumerate() {
+        let line = i + 1;
+        if raw.starts_with(char::is_whitespace) || raw.starts_with('#') {
+            continue
+        }
+        let key = match raw.split_once(':') {
+            Some((key, _)) => unquote(key.trim()).to_string(),
+            None => continue,
+        };
+        if let Some(first) = seen.get(&key) {
+            problems.push(Problem { file: file.name.clone(), line: Some(line), message: format!("duplicate key `{}` (first defined on line {})", key, first) });
+            continue
+        }
+        if let Some(value) = values.remove(&key) {
+            seen.insert(key.clone(), line);
+            out.push(Pair { line: Some(line), key, value });
+        }
+    }
+    let mut rest : Vec<(String, String)> = values.into_iter().collect();
+    rest.sort();
+    out.extend(rest.into_iter().map(|(key, value)| Pair { line: None, key, value }));
+    out
+}
+
+fn problem(file : &ConfigFile, pair : &Pair, message : String) -> Problem {
+    Problem { file: file.name.clone(), line: pair.line, message }
+}
+
+fn check_icons(file : &ConfigFile, default : &str, problems : &mut Vec<Problem>) -> Options {
+    let icons : Options = pairs(file, problems).into_iter().map(|pair| (pair.key, pair.value)).collect();
+    if !icons.contains_key(default) {
+        problems.push(Problem { file: file.name.clone(), line: None, message: format!("missing mandatory default icon `{}`", default) });
+    }
+    icons
+}
+
+fn check_aliases(file : &ConfigFile, icons : &Options, problems : &mut Vec<Problem>) {
+    let pairs = pairs(file, problems);
+    let aliases : Options = pairs.iter().map(|pair| (pair.key.clone(), pair.value.clone())).collect();
+    for pair in &pairs {
+        match resolve_alias(&pair.key, &aliases, icons) {
+            Ok(ref name) if !icons.contains_key(name) => {
+                problems.push(problem(file, pair, format!("alias `{}` points to unknown icon `{}`", pair.key, name)));
+            },
+            Ok(_) => {},
+            Err(e) => problems.push(problem(file, pair, e.to_string())),
+        }
+    }
+}
+
+fn check_colors(file : &ConfigFile, problems : &mut Vec<Problem>) {
+    for pair in pairs(file, problems) {
+        if ColorType::from_name(&pair.key).is_none() {
+            problems.push(problem(file, &pair, format!("unknown color type `{}`", pair.key)));
+        }
+        if RealColor::from_name(&pair.value).is_none() {
+            let names : Vec<&str> = RealColor::ALL.iter().map(|color| color.name()).collect();
+            problems.push(problem(file, &pair, format!("unknown color `{}` (expected one of {})", pair.value, names.join(", "))));
+        }
+    }
+}
+
+pub fn check_settings(file : &ConfigFile, problems : &mut Vec<Problem>) -> Options {
+    let mut settings = Options::new();
+    for pair in pairs(file, problems) {
+        match pair.key.as_str() {
+            "icon_theme" if IconTheme::from_name(&pair.value).is_none() => {
+                problems.push(problem(file, &pair, format!("unknown icon theme `{}`", pair.value)));
+            },
+            "icon_theme" => {
+                settings.insert(pair.key, pair.value);
+            },
+            _ => problems.push(problem(file, &pair, format!("unknown setting `{}`", pair.key))),
+        }
+    }
+    settings
+}
+
+pub fn check_config(config : &ConfigFiles) -> Vec<Problem> {
+    let mut problems = vec![];
+    let files = check_icons(&config.files, "file", &mut problems);
+    let folders = check_icons(&config.folders, "folder", &mut problems);
+    check_aliases(&config.file_aliases, &files, &mut problems);
+    check_aliases(&config.folder_aliases, &folders, &mut problems);
+    check_colors(&config.colors, &mut problems);
+    problems
+}
+
+#[cfg(test)]
+mod check_config_tests {
+    use super::*;
+
+    fn defaults() -> ConfigFiles {
+        let (files_name, files) = IconTheme::Nerd.files_source();
+        let (folders_name, folders) = IconTheme::Nerd.folders_source();
+        ConfigFiles {
+            files: ConfigFile::new(files_name, files),
+            file_aliases: ConfigFile::new("file_aliases.yaml", include_str!("default_config/file_aliases.yaml")),
+            folders: ConfigFile::new(folders_name, folders),
+            folder_aliases: ConfigFile::new("folder_aliases.yaml", include_str!("default_config/folder_aliases.yaml")),
+            colors: ConfigFile::new("colors.yaml", include_str!("default_config/dark_colors.yaml")),
+        }
+    }
+
+    fn messages(config : &ConfigFiles) -> Vec<String> {
+        check_config(config).iter().map(|problem| problem.to_string()).collect()
+    }
+
+    #[test]
+    fn for_simple_case() {
+        assert_eq!(Vec::<String>::new(), messages(&defaults()))
+    }
+
+    #[test]
+    fn for_light_colors() {
+        let config = ConfigFiles { colors: ConfigFile::new("colors.yaml", include_str!("default_config/light_colors.yaml")), ..defaults() };
+        assert_eq!(Vec::<String>::new(), messages(&config))
+    }
+
+    #[test]
+    fn when_color_is_unknown() {
+        let config = ConfigFiles { colors: ConfigFile::new("colors.yaml", "# Main\ndir: blue\nfolder: pink\n"), ..defaults() };
+        assert_eq!(vec![
+            "colors.yaml:3: unknown color type `folder`".to_string(),
+            "colors.yaml:3: unknown color `pink` (expected one of yellow, green, blue, red, cyan, magenta, grey, white, black)".to_string(),
+        ], messages(&config))
+    }
+
+    #[test]
+    fn when_alias_points_nowhere() {
+        let config = ConfigFiles { file_aliases: ConfigFile::new("file_aliases.yaml", "tsx: typescript_react\nmjs: js\n"), ..defaults() };
+        assert_eq!(vec!["file_aliases.yaml:1: alias `tsx` points to unknown icon `typescript_react`".to_string()], messages(&config))
+    }
+
+    #[test]
+    fn when_aliases_are_chained() {
+        let config = ConfigFiles { file_aliases: ConfigFile::new("file_aliases.yaml", "tsx: typescript_react\ntypescript_react: jsx\n"), ..defaults() };
+        assert_eq!(Vec::<String>::new(), messages(&config))
+    }
+
+    #[test]
+    fn when_aliases_form_a_cycle() {
+        let config = ConfigFiles { folder_aliases: ConfigFile::new("folder_aliases.yaml", "a: b\nb: a\n"), ..defaults() };
+        assert_eq!(vec![
+            "folder_aliases.yaml:1: alias cycle: a -> b -> a".to_string(),
+            "folder_aliases.yaml:2: alias cycle: b -> a -> b".to_string(),
+        ], messages(&config))
+    }
+
+    #[test]
+    fn when_default_is_missing() {
+        let config = ConfigFiles { folders: ConfigFile::new("folders.yaml", "git: \"G\"\n"), folder_aliases: ConfigFile::new("folder_aliases.yaml", ""), ..defaults() };
+        assert_eq!(vec!["folders.yaml: missing mandatory default icon `folder`".to_string()], messages(&config))
+    }
+
+    #[test]
+    fn when_key_is_duplicated() {
+        let config = ConfigFiles { colors: ConfigFile::new("colors.yaml", "dir: blue\nlink: cyan\ndir: red # again\n"), ..defaults() };
+        assert_eq!(vec!["colors.yaml:3: duplicate key `dir` (first defined on line 1)".to_string()], messages(&config))
+    }
+}
+
+#[cfg(test)]
+mod check_settings_tests {
+    use super::*;
+
+    fn check(contents : &str) -> (Options, Vec<String>) {
+        let mut problems = vec![];
+        let settings = check_settings(&ConfigFile::new("config.yaml", contents), &mut problems);
+        (settings, problems.iter().map(|problem| problem.to_string()).collect())
+    }
+
+    #[test]
+    fn for_simple_case() {
+        let (settings, messages) = check("icon_theme: emoji\n");
+        assert_eq!(Some(&"emoji".to_string()), settings.get("icon_theme"));
+        assert!(messages.is_empty())
+    }
+
+    #[test]
+    fn when_settings_are_wrong() {
+        let (settings, messages) = check("icon_theme: fancy\ncolours: dark\n");
+        assert!(settings.is_empty());
+        assert_eq!(vec![
+            "config.yaml:1: unknown icon theme `fancy`".to_string(),
+            "config.yaml:2: unknown setting `colours`".to_string(),
+        ], messages)
+    }
+
+    #[test]
+    fn when_settings_are_not_yaml() {
+        for contents in &["icon_theme: fancy\n- a\n", "icon_theme: [a\n"] {
+            let (_, messages) = check(contents);
+            assert_eq!(1, messages.len());
+            assert!(messages[0].starts_with("config.yaml: while parsing"), "{}", messages[0])
+        }
+    }
+
+    #[test]
+    fn when_values_use_yaml_escapes() {
+        let (settings, messages) = check("icon_theme: \"emoj\\u0069\" # quoted\n");
+        assert_eq!(Some(&"emoji".to_string()), settings.get("icon_theme"));
+        assert!(messages.is_empty())
+    }
+}
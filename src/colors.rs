@@ -21,6 +21,69 @@ pub enum ColorType {
     Tree,
     Empty,
     Normal,
+    SizeB,
+    SizeKb,
+    SizeMb,
+    SizeGb,
+    Indicator,
+}
+
+impl ColorType {
+    pub const ALL : [ColorType; 22] = [
+        ColorType::UnrecognizedFile,
+        ColorType::RecognizedFile,
+        ColorType::Dir,
+        ColorType::DeadLink,
+        ColorType::Link,
+        ColorType::Write,
+        ColorType::Read,
+        ColorType::Exec,
+        ColorType::NoAccess,
+        ColorType::DayOld,
+        ColorType::HourOld,
+        ColorType::NoModifier,
+        ColorType::Report,
+        ColorType::User,
+        ColorType::Tree,
+        ColorType::Empty,
+        ColorType::Normal,
+        ColorType::SizeB,
+        ColorType::SizeKb,
+        ColorType::SizeMb,
+        ColorType::SizeGb,
+        ColorType::Indicator,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ColorType::UnrecognizedFile => "unrecognized_file",
+            ColorType::RecognizedFile => "recognized_file",
+            ColorType::Dir => "dir",
+            ColorType::DeadLink => "dead_link",
+            ColorType::Link => "link",
+            ColorType::Write => "write",
+            ColorType::Read => "read",
+            ColorType::Exec => "exec",
+            ColorType::NoAccess => "no_access",
+            ColorType::DayOld => "day_old",
+            ColorType::HourOld => "hour_old",
+            ColorType::NoModifier => "no_modifier",
+            ColorType::Report => "report",
+            ColorType::User => "user",
+            ColorType::Tree => "tree",
+            ColorType::Empty => "empty",
+            ColorType::Normal => "normal",
+            ColorType::SizeB => "size_b",
+            ColorType::SizeKb => "size_kb",
+            ColorType::SizeMb => "size_mb",
+            ColorType::SizeGb => "size_gb",
+            ColorType::Indicator => "indicator",
+        }
+    }
+
+    pub fn from_name(name : &str) -> Option<ColorType> {
+        ColorType::ALL.iter().find(|color| color.name() == name).cloned()
+    }
 }
 
 struct ColorTypeVisitor;
@@ -28,32 +91,14 @@ impl Visitor for ColorTypeVisitor {
     type Value = ColorType;
 
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str("one of unrecognized_file, recognized_file, dir, dead_link, link, write, read, exec, no_access, day_old, hour_old, no_modifier, report, user, tree, empty, normal")
+        let names : Vec<&str> = ColorType::ALL.iter().map(|color| color.name()).collect();
+        write!(formatter, "one of {}", names.join(", "))
     }
 
     fn visit_str<E>(self, value: &str) -> Result<ColorType, E>
         where E: de::Error
     {
-        match value {
-            "unrecognized_file" => Ok(ColorType::UnrecognizedFile),
-            "recognized_file" => Ok(ColorType::RecognizedFile),
-            "dir" => Ok(ColorType::Dir),
-            "dead_link" => Ok(ColorType::DeadLink),
-            "link" => Ok(ColorType::Link),
-            "write" => Ok(ColorType::Write),
-            "read" => Ok(ColorType::Read),
-            "exec" => Ok(ColorType::Exec),
-            "no_access" => Ok(ColorType::NoAccess),
-            "day_old" => Ok(ColorType::DayOld),
-            "hour_old" => Ok(ColorType::HourOld),
-            "no_modifier" => Ok(ColorType::NoModifier),
-            "report" => Ok(ColorType::Report),
-            "user" => Ok(ColorType::User),
-            "tree" => Ok(ColorType::Tree),
-            "empty" => Ok(ColorType::Empty),
-            "normal" => Ok(ColorType::Normal),
-            _ => Err(E::custom(format!("Unknown ColorType: {}", value)))
-        }
+        ColorType::from_name(value).ok_or_else(|| E::custom(format!("Unknown ColorType: {}", value)))
     }
 }
 
@@ -78,29 +123,51 @@ pub enum RealColor {
     Black,
 }
 
+impl RealColor {
+    pub const ALL : [RealColor; 9] = [
+        RealColor::Yellow,
+        RealColor::Green,
+        RealColor::Blue,
+        RealColor::Red,
+        RealColor::Cyan,
+        RealColor::Magenta,
+        RealColor::Grey,
+        RealColor::White,
+        RealColor::Black,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            RealColor::Yellow => "yellow",
+            RealColor::Green => "green",
+            RealColor::Blue => "blue",
+            RealColor::Red => "red",
+            RealColor::Cyan => "cyan",
+            RealColor::Magenta => "magenta",
+            RealColor::Grey => "grey",
+            RealColor::White => "white",
+            RealColor::Black => "black",
+        }
+    }
+
+    pub fn from_name(name : &str) -> Option<RealColor> {
+        RealColor::ALL.iter().find(|color| color.name() == name).cloned()
+    }
+}
+
 struct RealColorVisitor;
 impl Visitor for RealColorVisitor {
     type Value = RealColor;
 
     fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-        formatter.write_str("one of yellow, green, blue, red, cyan, magenta, grey, white, black")
+        let names : Vec<&str> = RealColor::ALL.iter().map(|color| color.name()).collect();
+        write!(formatter, "one of {}", names.join(", "))
     }
 
     fn visit_str<E>(self, value: &str) -> Result<RealColor, E>
         where E: de::Error
     {
-        match value {
-            "yellow" => Ok(RealColor::Yellow),
-            "green" => Ok(RealColor::Green),
-            "blue" => Ok(RealColor::Blue),
-            "red" => Ok(RealColor::Red),
-            "cyan" => Ok(RealColor::Cyan),
-            "magenta" => Ok(RealColor::Magenta),
-            "grey" => Ok(RealColor::Grey),
-            "white" => Ok(RealColor::White),
-            "black" => Ok(RealColor::Black),
-            _ => Err(E::custom(format!("Unknown RealColor: {}", value)))
-        }
+        RealColor::from_name(value).ok_or_else(|| E::custom(format!("Unknown RealColor: {}", value)))
     }
 }
 
@@ -112,7 +179,7 @@ impl Deserialize for RealColor {
     }
 }
 
-pub struct ColorWrapper(pub Box<color::Color>);
+pub struct ColorWrapper(pub Box<dyn color::Color>);
 
 impl color::Color for ColorWrapper {
     #[inline]
@@ -0,0 +1,127 @@
+use std::collections::HashSet;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path;
+use std::sync::Mutex;
+
+use rayon::prelude::*;
+
+// NOTE: Sizes are apparent sizes (like `du --apparent-size`), so they match the file sizes in long format.
+#[derive(Debug)]
+pub struct DiskUsage {
+    one_file_system: bool,
+    seen: Mutex<HashSet<(u64, u64)>>,
+}
+
+impl DiskUsage {
+    // NOTE: One per run, so a file hard linked into several measured directories counts once, for the first one measured.
+    pub fn new(one_file_system : bool) -> DiskUsage {
+        DiskUsage { one_file_system, seen: Mutex::new(HashSet::new()) }
+    }
+
+    pub fn measure(&self, path : &path::Path, metadata : &fs::Metadata) -> u64 {
+        self.size(path, metadata, metadata.dev())
+    }
+
+    fn size(&self, path : &path::Path, metadata : &fs::Metadata, root_dev : u64) -> u64 {
+        if metadata.is_dir() {
+            metadata.len() + self.walk(path, root_dev)
+        } else if metadata.nlink() > 1 && !self.seen.lock().unwrap().insert((metadata.dev(), metadata.ino())) {
+            0
+        } else {
+            metadata.len()
+        }
+    }
+
+    fn walk(&self, path : &path::Path, root_dev : u64) -> u64 {
+        let entries : Vec<path::PathBuf> = match fs::read_dir(path) {
+            Ok(dirs) => dirs.filter_map(|dir| dir.ok()).map(|dir| dir.path()).collect(),
+            Err(_) => return 0,
+        };
+        entries.par_iter().map(|path| {
+            match fs::symlink_metadata(path) {
+                Ok(ref metadata) if self.skips(metadata.is_dir(), metadata.dev(), root_dev) => 0,
+                Ok(ref metadata) => self.size(path, metadata, root_dev),
+                Err(_) => 0,
+            }
+        }).sum()
+    }
+
+    fn skips(&self, is_dir : bool, dev : u64, root_dev : u64) -> bool {
+        is_dir && self.one_file_system && dev != root_dev
+    }
+}
+
+#[cfg(test)]
+mod disk_usage_tests {
+    use super::*;
+    use self::super::super::testing::TempDir;
+
+    fn temp_dir() -> TempDir {
+        let dir = TempDir::new("disk_usage");
+        fs::create_dir(dir.join("nested")).unwrap();
+        dir
+    }
+
+    fn dir_len(path : &path::Path) -> u64 {
+        fs::symlink_metadata(path).unwrap().len()
+    }
+
+    #[test]
+    fn for_simple_case() {
+        let temp = temp_dir();
+        let dir = temp.path();
+        fs::write(dir.join("a"), "1234").unwrap();
+        fs::write(dir.join("nested").join("b"), "123456").unwrap();
+        assert_eq!(10 + dir_len(dir) + dir_len(&dir.join("nested")), DiskUsage::new(false).measure(dir, &fs::symlink_metadata(dir).unwrap()))
+    }
+
+    #[test]
+    fn when_files_are_hard_linked() {
+        let temp = temp_dir();
+        let dir = temp.path();
+        fs::write(dir.join("a"), "1234").unwrap();
+        fs::hard_link(dir.join("a"), dir.join("nested").join("b")).unwrap();
+        assert_eq!(4 + dir_len(dir) + dir_len(&dir.join("nested")), DiskUsage::new(false).measure(dir, &fs::symlink_metadata(dir).unwrap()))
+    }
+
+    #[test]
+    fn when_hard_links_span_measured_directories() {
+        let temp = temp_dir();
+        let dir = temp.path();
+        fs::create_dir(dir.join("other")).unwrap();
+        fs::write(dir.join("nested").join("a"), "1234").unwrap();
+        fs::hard_link(dir.join("nested").join("a"), dir.join("other").join("b")).unwrap();
+        let du = DiskUsage::new(false);
+        let measure = |name : &str| du.measure(&dir.join(name), &fs::symlink_metadata(dir.join(name)).unwrap());
+        assert_eq!(4 + dir_len(&dir.join("nested")), measure("nested"));
+        assert_eq!(dir_len(&dir.join("other")), measure("other"))
+    }
+
+    #[test]
+    fn when_staying_on_one_file_system() {
+        let temp = temp_dir();
+        let dir = temp.path();
+        fs::write(dir.join("a"), "1234").unwrap();
+        fs::write(dir.join("nested").join("b"), "123456").unwrap();
+        let metadata = fs::symlink_metadata(dir).unwrap();
+        // NOTE: A root device nothing is on makes every subdirectory look like a mount point.
+        assert_eq!(4 + dir_len(dir), DiskUsage::new(true).size(dir, &metadata, u64::MAX));
+        assert_eq!(10 + dir_len(dir) + dir_len(&dir.join("nested")), DiskUsage::new(false).size(dir, &metadata, u64::MAX))
+    }
+
+    #[test]
+    fn for_device_comparison() {
+        assert!(DiskUsage::new(true).skips(true, 2, 1));
+        assert!(!DiskUsage::new(true).skips(true, 1, 1));
+        assert!(!DiskUsage::new(true).skips(false, 2, 1));
+        assert!(!DiskUsage::new(false).skips(true, 2, 1))
+    }
+
+    #[test]
+    fn when_directory_is_gone() {
+        let temp = temp_dir();
+        let metadata = fs::symlink_metadata(temp.path()).unwrap();
+        assert_eq!(metadata.len(), DiskUsage::new(false).measure(path::Path::new("/nonexistent/colorls"), &metadata))
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use self::super::colors::ColorType;
+use self::super::formatter::Options;
+use self::super::Action;
+
+#[derive(Hash, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DumpFormat {
+    Yaml,
+    Json,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Origin {
+    Default,
+    Builtin(String),
+    Settings(String),
+    CommandLine,
+    Terminal,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f : &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Origin::Default => write!(f, "default"),
+            Origin::Builtin(ref file) => write!(f, "builtin {}", file),
+            Origin::Settings(ref file) => write!(f, "{}", file),
+            Origin::CommandLine => write!(f, "command line"),
+            Origin::Terminal => write!(f, "terminal"),
+        }
+    }
+}
+
+pub type Origins = HashMap<&'static str, Origin>;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Value {
+    Text(String),
+    Number(usize),
+    Flag(bool),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Setting {
+    pub name: String,
+    pub value: Value,
+    pub origin: Origin,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ConfigDump {
+    pub sections: Vec<(&'static str, Vec<Setting>)>,
+}
+
+fn origin_of(origins : &Origins, name : &str) -> Origin {
+    origins.get(name).cloned().unwrap_or(Origin::Default)
+}
+
+fn table(options : &Options, origin : &Origin) -> Vec<Setting> {
+    let mut settings : Vec<Setting> = options.iter().map(|(name, value)| Setting { name: name.clone(), value: Value::Text(value.clone()), origin: origin.clone() }).collect();
+    settings.sort_by(|a, b| a.name.cmp(&b.name));
+    settings
+}
+
+fn escape(text : &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn scalar(value : &Value) -> String {
+    match *value {
+        Value::Text(ref text) => escape(text),
+        Value::Number(number) => number.to_string(),
+        Value::Flag(flag) => flag.to_string(),
+    }
+}
+
+impl ConfigDump {
+    pub fn new(action : &Action, origins : &Origins) -> ConfigDump {
+        let entry = &action.config.entry;
+        let text = |name : &str| Value::Text(name.to_string());
+        let display : Vec<(&'static str, Value)> = vec![
+            ("format", text(action.config.formatter.name())),
+            ("tabulator", text(action.tabulator.name())),
+            ("output", text(action.renderer.name())),
+            ("entry_order", text(action.config.entry_order.name())),
+            ("width", Value::Number(action.config.max_width)),
+            ("sort", text(action.sort.name())),
+            ("stream", Value::Flag(action.stream)),
+            ("du", Value::Flag(action.du)),
+            ("one_file_system", Value::Flag(action.one_file_system)),
+            ("report", text(action.report.map(|style| style.name()).unwrap_or("none"))),
+            ("icon_theme", text(entry.icon_theme.name())),
+            ("indicator_style", text(action.indicator_style.name())),
+            ("quoting_style", text(entry.quoting_style.name())),
+            ("hyperlink", Value::Flag(entry.hyperlink)),
+            ("size_units", Value::Text(entry.size_format.units.name())),
+            ("size_precision", entry.size_format.precision.map(Value::Number).unwrap_or_else(|| text("auto"))),
+            ("time_field", text(entry.time_format.field.name())),
+            ("time_style", Value::Text(entry.time_format.style.name())),
+            ("show_inode", Value::Flag(entry.show_inode)),
+            ("show_blocks", Value::Flag(entry.show_blocks)),
+            ("show_links", Value::Flag(entry.show_links)),
+            ("show_xattr", Value::Flag(entry.show_xattr)),
+            ("show_context", Value::Flag(entry.show_context)),
+            ("check_empty_dirs", Value::Flag(entry.check_empty_dirs)),
+            ("parallel_threshold", Value::Number(action.parallel_threshold)),
+        ];
+        let display = display.into_iter().map(|(name, value)| Setting { name: name.to_string(), value, origin: origin_of(origins, name) }).collect();
+        let colors_origin = origin_of(origins, "colors");
+        let colors = ColorType::ALL.iter().filter_map(|color| entry.colors.get(color).map(|real| Setting {
+            name: color.name().to_string(),
+            value: Value::Text(real.name().to_string()),
+            origin: colors_origin.clone(),
+        })).collect();
+        ConfigDump {
+            sections: vec![
+                ("display", display),
+                ("files", table(&entry.files, &origin_of(origins, "files"))),
+                ("file_aliases", table(&entry.file_aliases, &origin_of(origins, "file_aliases"))),
+                ("folders", table(&entry.folders, &origin_of(origins, "folders"))),
+                ("folder_aliases", table(&entry.folder_aliases, &origin_of(origins, "folder_aliases"))),
+                ("colors", colors),
+            ],
+        }
+    }
+
+    // NOTE: Text is always double quoted and numbers and flags never are, which reads the same in both YAML and JSON.
+    pub fn format(&self, format : DumpFormat) -> String {
+        let mut out = String::new();
+        match format {
+            DumpFormat::Yaml => {
+                out.push_str("# Effective configuration, every value is followed by where it came from.\n");
+                for &(section, ref settings) in &self.sections {
+                    out.push_str(&format!("{}:\n", section));
+                    for setting in settings {
+                        out.push_str(&format!("  {}: {} # {}\n", escape(&setting.name), scalar(&setting.value), setting.origin));
+                    }
+                }
+            },
+            DumpFormat::Json => {
+                let sections : Vec<String> = self.sections.iter().map(|&(section, ref settings)| {
+                    let settings : Vec<String> = settings.iter().map(|setting| {
+                        format!("    {}: {{\"value\": {}, \"origin\": {}}}", escape(&setting.name), scalar(&setting.value), escape(&setting.origin.to_string()))
+                    }).collect();
+                    format!("  {}: {{\n{}\n  }}", escape(section), settings.join(",\n"))
+                }).collect();
+                out.push_str(&format!("{{\n{}\n}}\n", sections.join(",\n")));
+            },
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod config_dump_tests {
+    use super::*;
+    use self::super::super::icons::IconTheme;
+    use self::super::super::indicator::IndicatorStyle;
+    use self::super::super::quoting::QuotingStyle;
+    use self::super::super::size::parse_block_size;
+    use self::super::super::time::{TimeStyle, parse_time_style};
+
+    fn dump() -> ConfigDump {
+        ConfigDump {
+            sections: vec![
+                ("display", vec![Setting { name: "icon_theme".to_string(), value: Value::Text("Emoji".to_string()), origin: Origin::Settings("/home/config.yaml".to_string()) }]),
+                ("files", vec![Setting { name: "rs".to_string(), value: Value::Text("\"R\"".to_string()), origin: Origin::Builtin("files.yaml".to_string()) }]),
+            ],
+        }
+    }
+
+    #[test]
+    fn for_yaml() {
+        assert_eq!(
+            "# Effective configuration, every value is followed by where it came from.\ndisplay:\n  \"icon_theme\": \"Emoji\" # /home/config.yaml\nfiles:\n  \"rs\": \"\\\"R\\\"\" # builtin files.yaml\n",
+            dump().format(DumpFormat::Yaml))
+    }
+
+    #[test]
+    fn for_json() {
+        assert_eq!(
+            "{\n  \"display\": {\n    \"icon_theme\": {\"value\": \"Emoji\", \"origin\": \"/home/config.yaml\"}\n  },\n  \"files\": {\n    \"rs\": {\"value\": \"\\\"R\\\"\", \"origin\": \"builtin files.yaml\"}\n  }\n}\n",
+            dump().format(DumpFormat::Json))
+    }
+
+    #[test]
+    fn for_numbers_and_flags() {
+        let dump = ConfigDump {
+            sections: vec![("display", vec![
+                Setting { name: "width".to_string(), value: Value::Number(80), origin: Origin::Terminal },
+                Setting { name: "du".to_string(), value: Value::Flag(true), origin: Origin::CommandLine },
+            ])],
+        };
+        assert_eq!(
+            "{\n  \"display\": {\n    \"width\": {\"value\": 80, \"origin\": \"terminal\"},\n    \"du\": {\"value\": true, \"origin\": \"command line\"}\n  }\n}\n",
+            dump.format(DumpFormat::Json));
+        assert!(dump.format(DumpFormat::Yaml).contains("  \"width\": 80 # terminal\n"))
+    }
+
+    #[test]
+    fn when_values_are_read_back() {
+        let themes = [IconTheme::Nerd, IconTheme::Emoji, IconTheme::Ascii, IconTheme::None];
+        let indicators = [IndicatorStyle::None, IndicatorStyle::Slash, IndicatorStyle::FileType, IndicatorStyle::Classify];
+        let quoting = [QuotingStyle::Literal, QuotingStyle::HideControl, QuotingStyle::Shell, QuotingStyle::ShellEscape, QuotingStyle::C, QuotingStyle::Escape];
+        let time_styles = [TimeStyle::Locale, TimeStyle::Iso, TimeStyle::LongIso, TimeStyle::FullIso, TimeStyle::Relative, TimeStyle::Format("%H:%M".to_string())];
+        let block_sizes = ["1", "human-readable", "si", "K", "MB", "MiB", "4096"];
+        let table : Vec<(String, Option<String>)> = themes.iter().map(|v| (v.name().to_string(), IconTheme::from_name(v.name()).map(|v| v.name().to_string())))
+            .chain(indicators.iter().map(|v| (v.name().to_string(), IndicatorStyle::from_name(v.name()).map(|v| v.name().to_string()))))
+            .chain(quoting.iter().map(|v| (v.name().to_string(), QuotingStyle::from_name(v.name()).map(|v| v.name().to_string()))))
+            .chain(time_styles.iter().map(|v| (v.name(), parse_time_style(&v.name()).ok().map(|v| v.name()))))
+            .chain(block_sizes.iter().map(|v| (v.to_string(), parse_block_size(v).ok().map(|v| v.name()))))
+            .collect();
+        for (name, read_back) in table {
+            assert_eq!(Some(&name), read_back.as_ref())
+        }
+    }
+
+    #[test]
+    fn for_control_characters() {
+        assert_eq!("\"a\\u001bb\\n\"", escape("a\x1bb\n"))
+    }
+}
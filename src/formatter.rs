@@ -1,14 +1,25 @@
 use std::collections::HashMap;
 use std::path;
-use std::cmp::Ordering;
+use std::fs;
+use std::os::unix::fs::{PermissionsExt, MetadataExt};
+use std::sync::OnceLock;
+use std::cmp::{max, Ordering};
 use std::ffi;
 use std::fmt;
+use std::time::{SystemTime, Duration};
 
 use unicode_segmentation::UnicodeSegmentation;
 
 use termion::color;
 
 use self::super::colors::{ColorType, RealColor, ColorWrapper};
+use self::super::size::{SizeFormat, SizeUnits};
+use self::super::time::{TimeFormat, TimeField, TimeStyle};
+use self::super::icons::IconTheme;
+use self::super::quoting::QuotingStyle;
+use self::super::hyperlink::file_url;
+use self::super::cell::{Cell, Span};
+use self::super::permissions::{mode_string, mode_color};
 
 pub type Options = HashMap<String, String>;
 
@@ -19,19 +30,89 @@ pub struct EntryConfig {
     pub folders: Options,
     pub folder_aliases: Options,
     pub colors: HashMap<ColorType, RealColor>,
-    pub width: usize,
+    pub icon_theme: IconTheme,
+    pub quoting_style: QuotingStyle,
+    pub show_inode: bool,
+    pub show_blocks: bool,
+    pub show_links: bool,
+    pub show_xattr: bool,
+    pub show_context: bool,
+    pub hyperlink: bool,
+    pub hostname: String,
+    pub current_dir: path::PathBuf,
+    pub check_empty_dirs: bool,
+    pub size_format: SizeFormat,
+    pub time_format: TimeFormat,
+    pub now: SystemTime,
+    pub columns: ColumnWidths,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct ColumnWidths {
+    pub inode: usize,
+    pub blocks: usize,
+    pub links: usize,
+    pub mode: usize,
+    pub context: usize,
+    pub size: usize,
+    pub time: usize,
+}
+
+#[cfg(test)]
+impl Default for EntryConfig {
+    fn default() -> EntryConfig {
+        EntryConfig {
+            files: Options::new(),
+            file_aliases: Options::new(),
+            folders: Options::new(),
+            folder_aliases: Options::new(),
+            colors: HashMap::new(),
+            icon_theme: IconTheme::Nerd,
+            quoting_style: QuotingStyle::Literal,
+            show_inode: false,
+            show_blocks: false,
+            show_links: false,
+            show_xattr: false,
+            show_context: false,
+            hyperlink: false,
+            hostname: String::new(),
+            current_dir: path::PathBuf::new(),
+            check_empty_dirs: true,
+            size_format: SizeFormat::default(),
+            time_format: TimeFormat::default(),
+            now: SystemTime::now(),
+            columns: ColumnWidths::default(),
+        }
+    }
+}
+
+impl ColumnWidths {
+    pub fn widen(&mut self, other : &ColumnWidths) {
+        self.inode = max(self.inode, other.inode);
+        self.blocks = max(self.blocks, other.blocks);
+        self.links = max(self.links, other.links);
+        self.mode = max(self.mode, other.mode);
+        self.context = max(self.context, other.context);
+        self.size = max(self.size, other.size);
+        self.time = max(self.time, other.time);
+    }
 }
 
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub struct Attr {
-    icon: String,
-    color: ColorType,
+    pub icon: String,
+    pub color: ColorType,
+    pub recognized: bool,
 }
 
+// NOTE: Logical icons share the tables with real names, but are only ever picked by `get_special_attr`.
+const SPECIAL_FILE_ICONS : [&str; 2] = ["exec", "empty"];
+const SPECIAL_FOLDER_ICONS : [&str; 1] = ["empty"];
+
 fn get_file_attr(conf : &EntryConfig, suffix : &str) -> Attr {
     match conf.files.get(suffix) {
-        Some(icon) => Attr { icon: icon.clone(), color: ColorType::RecognizedFile },
-        None => Attr { icon: conf.files.get("file").unwrap().clone(), color: ColorType::UnrecognizedFile }
+        Some(icon) if !SPECIAL_FILE_ICONS.contains(&suffix) => Attr { icon: icon.clone(), color: ColorType::RecognizedFile, recognized: true },
+        _ => Attr { icon: conf.files.get("file").unwrap().clone(), color: ColorType::UnrecognizedFile, recognized: false }
     }
 }
 
@@ -44,8 +125,8 @@ fn get_file_attr_alias(conf : &EntryConfig, suffix : &str) -> Attr {
 
 fn get_folder_attr(conf : &EntryConfig, name : &str) -> Attr {
     match conf.folders.get(name) {
-        Some(icon) => Attr { icon: icon.clone(), color: ColorType::Dir },
-        None => Attr { icon: conf.folders.get("folder").unwrap().clone(), color: ColorType::Dir }
+        Some(icon) if !SPECIAL_FOLDER_ICONS.contains(&name) => Attr { icon: icon.clone(), color: ColorType::Dir, recognized: true },
+        _ => Attr { icon: conf.folders.get("folder").unwrap().clone(), color: ColorType::Dir, recognized: false }
     }
 }
 
@@ -56,43 +137,169 @@ fn get_folder_attr_alias(conf : &EntryConfig, name : &str) -> Attr {
     }
 }
 
+fn get_special_attr(icons : &Options, key : &str, color : ColorType, attr : Attr) -> Attr {
+    let icon = if attr.recognized {
+        attr.icon
+    } else {
+        icons.get(key).unwrap_or(&attr.icon).clone()
+    };
+    Attr { icon, color, ..attr }
+}
+
+fn is_executable(metadata : &fs::Metadata) -> bool {
+    metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+}
+
+fn is_empty_file(metadata : &fs::Metadata) -> bool {
+    metadata.is_file() && metadata.len() == 0
+}
+
+fn is_empty_dir(path : &path::Path) -> bool {
+    match fs::read_dir(path) {
+        Ok(mut entries) => entries.next().is_none(),
+        Err(_) => false,
+    }
+}
+
 fn filename_without_leading_dot(path : &path::Path) -> String {
-    let mut file_name = path.file_name().unwrap().to_str().unwrap().to_string();
+    let mut file_name = path.file_name().unwrap().to_string_lossy().into_owned();
     file_name.remove(0);
     file_name
 }
 
-pub fn get_attr(config : &EntryConfig, path : &path::Path) -> Attr {
-    if path.is_dir() {
-        let file_name = path.file_name().unwrap().to_str().unwrap();
-        return get_folder_attr_alias(config, file_name)
+pub fn get_attr(config : &EntryConfig, entry : &Entry) -> Attr {
+    let path = &entry.path;
+    if entry.is_dir() {
+        let file_name = path.file_name().unwrap().to_string_lossy();
+        let attr = get_folder_attr_alias(config, &file_name);
+        if config.check_empty_dirs && is_empty_dir(path) {
+            get_special_attr(&config.folders, "empty", ColorType::Empty, attr)
+        } else {
+            attr
+        }
     } else {
         let filename_without_leading_dot = filename_without_leading_dot(path);
         let default = ffi::OsStr::new(&filename_without_leading_dot);
-        let extension = path.extension().unwrap_or(default).to_str().unwrap();
-        return get_file_attr_alias(config, extension)
-    }
-}
-
-fn color_for(config : &EntryConfig, color : &ColorType) -> ColorWrapper {
-    let boxed : Box<color::Color> = match config.colors.get(color).unwrap_or(&RealColor::Grey) {
-        &RealColor::Yellow => Box::new(color::Yellow),
-        &RealColor::Green => Box::new(color::Green),
-        &RealColor::Blue => Box::new(color::Blue),
-        &RealColor::Red => Box::new(color::Red),
-        &RealColor::Cyan => Box::new(color::Cyan),
-        &RealColor::Magenta => Box::new(color::Magenta),
-        &RealColor::Grey => Box::new(color::AnsiValue::rgb(2,2,2)),
-        &RealColor::White => Box::new(color::AnsiValue::rgb(0,0,0)),
-        &RealColor::Black => Box::new(color::AnsiValue::rgb(5,5,5)),
+        let extension = path.extension().unwrap_or(default).to_string_lossy();
+        let attr = get_file_attr_alias(config, &extension);
+        match entry.lstat() {
+            Some(metadata) if is_empty_file(metadata) => get_special_attr(&config.files, "empty", ColorType::Empty, attr),
+            Some(metadata) if is_executable(metadata) => get_special_attr(&config.files, "exec", ColorType::Exec, attr),
+            _ => attr,
+        }
+    }
+}
+
+pub fn color_for(config : &EntryConfig, color : &ColorType) -> ColorWrapper {
+    let boxed : Box<dyn color::Color> = match *config.colors.get(color).unwrap_or(&RealColor::Grey) {
+        RealColor::Yellow => Box::new(color::Yellow),
+        RealColor::Green => Box::new(color::Green),
+        RealColor::Blue => Box::new(color::Blue),
+        RealColor::Red => Box::new(color::Red),
+        RealColor::Cyan => Box::new(color::Cyan),
+        RealColor::Magenta => Box::new(color::Magenta),
+        RealColor::Grey => Box::new(color::AnsiValue::rgb(2,2,2)),
+        RealColor::White => Box::new(color::AnsiValue::rgb(0,0,0)),
+        RealColor::Black => Box::new(color::AnsiValue::rgb(5,5,5)),
     };
     ColorWrapper(boxed)
 }
 
-#[derive(Eq, Clone, Debug)]
+// NOTE: Per thread, so each test only sees the calls of the entries it loads itself.
+#[cfg(test)]
+thread_local! {
+    pub static METADATA_CALLS : std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+#[cfg(test)]
+fn count_metadata_call() {
+    METADATA_CALLS.with(|calls| calls.set(calls.get() + 1));
+}
+
+#[derive(Clone, Debug)]
 pub struct Entry {
     pub path: path::PathBuf,
     pub attr: Attr,
+    pub file_type: Option<fs::FileType>,
+    pub indicator: &'static str,
+    pub xattr: &'static str,
+    pub context: Option<String>,
+    pub disk_usage: Option<u64>,
+    lstat: OnceLock<Option<fs::Metadata>>,
+    stat: OnceLock<Option<fs::Metadata>>,
+}
+
+impl Entry {
+    pub fn new(path : path::PathBuf, file_type : Option<fs::FileType>) -> Entry {
+        Entry {
+            path,
+            attr: Attr { icon: String::new(), color: ColorType::Normal, recognized: false },
+            file_type,
+            indicator: "",
+            xattr: "",
+            context: None,
+            disk_usage: None,
+            lstat: OnceLock::new(),
+            stat: OnceLock::new(),
+        }
+    }
+
+    pub fn lstat(&self) -> Option<&fs::Metadata> {
+        self.lstat.get_or_init(|| {
+            #[cfg(test)]
+            count_metadata_call();
+            fs::symlink_metadata(&self.path).ok()
+        }).as_ref()
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        match self.file_type {
+            Some(file_type) => file_type.is_symlink(),
+            None => self.lstat().map(|metadata| metadata.file_type().is_symlink()).unwrap_or(false),
+        }
+    }
+
+    pub fn stat(&self) -> Option<&fs::Metadata> {
+        if !self.is_symlink() {
+            return self.lstat()
+        }
+        self.stat.get_or_init(|| {
+            #[cfg(test)]
+            count_metadata_call();
+            fs::metadata(&self.path).ok()
+        }).as_ref()
+    }
+
+    pub fn is_dir(&self) -> bool {
+        match self.file_type {
+            Some(file_type) if !file_type.is_symlink() => file_type.is_dir(),
+            _ => self.stat().map(|metadata| metadata.is_dir()).unwrap_or(false),
+        }
+    }
+
+    pub fn size(&self) -> u64 {
+        self.disk_usage.unwrap_or_else(|| self.lstat().map(|metadata| metadata.len()).unwrap_or(0))
+    }
+
+    pub fn time(&self, field : TimeField) -> Option<SystemTime> {
+        self.lstat().and_then(|metadata| field.of(metadata))
+    }
+
+    pub fn inode(&self) -> u64 {
+        self.lstat().map(|metadata| metadata.ino()).unwrap_or(0)
+    }
+
+    pub fn blocks(&self) -> u64 {
+        self.lstat().map(|metadata| metadata.blocks()).unwrap_or(0)
+    }
+
+    pub fn links(&self) -> u64 {
+        self.lstat().map(|metadata| metadata.nlink()).unwrap_or(0)
+    }
+
+    pub fn mode(&self) -> u32 {
+        self.lstat().map(|metadata| metadata.mode()).unwrap_or(0)
+    }
 }
 
 impl Ord for Entry {
@@ -113,77 +320,418 @@ impl PartialEq for Entry {
     }
 }
 
+impl Eq for Entry {}
+
 pub trait Formatter: fmt::Debug {
-    fn format(&self, &EntryConfig, &Entry) -> String;
-    fn predict(&self, &Entry) -> usize;
+    fn format(&self, config : &EntryConfig, entry : &Entry, width : usize) -> Cell;
+    fn predict(&self, config : &EntryConfig, entry : &Entry) -> usize;
+
+    fn name(&self) -> &'static str {
+        "custom"
+    }
+}
+
+fn format_time(config : &EntryConfig, entry : &Entry) -> String {
+    match entry.time(config.time_format.field) {
+        Some(time) => config.time_format.format(time, config.now),
+        None => "-".to_string(),
+    }
+}
+
+fn time_color(config : &EntryConfig, entry : &Entry) -> ColorType {
+    match entry.time(config.time_format.field) {
+        Some(time) => config.time_format.color(time, config.now),
+        None => ColorType::NoModifier,
+    }
+}
+
+// NOTE: Blocks are counted in 512 bytes, but like ls printed in 1K units unless scaled.
+fn format_blocks(config : &EntryConfig, entry : &Entry) -> String {
+    match config.size_format.units {
+        SizeUnits::Bytes => format!("{}", (entry.blocks() * 512).div_ceil(1024)),
+        _ => config.size_format.format(entry.blocks() * 512),
+    }
+}
+
+fn format_mode(config : &EntryConfig, entry : &Entry, cell : &mut Cell) {
+    for c in mode_string(entry.mode()).chars() {
+        cell.colored(mode_color(c), c.to_string());
+    }
+    cell.text(format!("{xattr:<width$}", xattr = entry.xattr, width = config.columns.mode.saturating_sub(10)));
+}
+
+fn format_context(entry : &Entry) -> &str {
+    entry.context.as_deref().unwrap_or("?")
+}
+
+fn format_prefix(config : &EntryConfig, entry : &Entry, long : bool, cell : &mut Cell) {
+    if config.show_inode {
+        cell.text(format!("{:>width$} ", entry.inode(), width = config.columns.inode));
+    }
+    if config.show_blocks {
+        cell.text(format!("{:>width$} ", format_blocks(config, entry), width = config.columns.blocks));
+    }
+    if long {
+        format_mode(config, entry, cell);
+        cell.text(" ".to_string());
+    }
+    if long || config.show_links {
+        cell.text(format!("{:>width$} ", entry.links(), width = config.columns.links));
+    }
+    if config.show_context {
+        cell.text(format!("{:<width$} ", format_context(entry), width = config.columns.context));
+    }
+}
+
+pub fn column_widths(config : &EntryConfig, entries : &[Entry]) -> ColumnWidths {
+    let mut widths = ColumnWidths::default();
+    for entry in entries {
+        widths.inode = max(widths.inode, entry.inode().to_string().len());
+        widths.blocks = max(widths.blocks, strlen(&format_blocks(config, entry)));
+        widths.links = max(widths.links, entry.links().to_string().len());
+        widths.mode = max(widths.mode, 10 + entry.xattr.len());
+        widths.context = max(widths.context, strlen(format_context(entry)));
+        widths.size = max(widths.size, strlen(&config.size_format.format(entry.size())));
+        widths.time = max(widths.time, strlen(&format_time(config, entry)));
+    }
+    widths
+}
+
+pub fn minimum_widths(config : &EntryConfig) -> ColumnWidths {
+    let time = match config.time_format.style {
+        TimeStyle::Relative | TimeStyle::Format(_) => 0,
+        _ => {
+            let old = config.now - Duration::from_secs(365 * 24 * 60 * 60);
+            max(strlen(&config.time_format.format(config.now, config.now)), strlen(&config.time_format.format(old, config.now)))
+        },
+    };
+    ColumnWidths { mode: 10, size: config.size_format.widest(), time, ..ColumnWidths::default() }
+}
+
+fn format_name(config : &EntryConfig, entry : &Entry, name : String, cell : &mut Cell) {
+    if config.hyperlink {
+        cell.push(Span::Link { color: entry.attr.color, text: name, url: file_url(&config.hostname, &config.current_dir, &entry.path) });
+    } else {
+        cell.colored(entry.attr.color, name);
+    }
+    if !entry.indicator.is_empty() {
+        cell.colored(ColorType::Indicator, entry.indicator.to_string());
+    }
+}
+
+fn format_icon(config : &EntryConfig, entry : &Entry, long : bool, cell : &mut Cell) {
+    cell.push(Span::Icon { glyph: entry.attr.icon.clone(), width: config.icon_theme.icon_width() });
+    cell.text(config.icon_theme.gap(long).to_string());
 }
 
 #[derive(Debug)]
 pub struct LongFormat;
 impl Formatter for LongFormat {
-    fn format(&self, config :  &EntryConfig, entry : &Entry) -> String {
-        let name = entry.path.display();
-        let width = config.width - 2;
-        format!("{icon} {color}{name:<width$}{reset}",
-                name = name,
-                icon = entry.attr.icon,
-                color = color::Fg(color_for(config, &entry.attr.color)),
-                reset = color::Fg(color::Reset),
-                width = width,
-        )
+    fn name(&self) -> &'static str {
+        "long"
     }
 
-    fn predict(&self, entry : &Entry) -> usize {
-        strlen(&format!("{}", entry.path.display())) + 4
+    fn format(&self, config :  &EntryConfig, entry : &Entry, width : usize) -> Cell {
+        let mut cell = Cell::new();
+        format_prefix(config, entry, true, &mut cell);
+        cell.colored(config.size_format.color(entry.size()), format!("{:>width$}", config.size_format.format(entry.size()), width = config.columns.size));
+        cell.text(" ".to_string());
+        cell.colored(time_color(config, entry), format!("{:<width$}", format_time(config, entry), width = config.columns.time));
+        cell.text(" ".to_string());
+        format_icon(config, entry, true, &mut cell);
+        format_name(config, entry, config.quoting_style.quote(entry.path.as_os_str()), &mut cell);
+        // NOTE: Pads after the indicator, so it sticks to the name like in ls.
+        cell.pad_to(width.saturating_sub(1));
+        cell
+    }
+
+    fn predict(&self, config : &EntryConfig, entry : &Entry) -> usize {
+        self.format(config, entry, 0).width() + 3
     }
 }
 
 #[derive(Debug)]
 pub struct ShortFormat;
 
-fn short_name(l : &Entry) -> String {
-    l.path.file_name().unwrap().to_str().unwrap().to_string()
+fn short_name(config : &EntryConfig, l : &Entry) -> String {
+    config.quoting_style.quote(l.path.file_name().unwrap())
 }
 
 impl Formatter for ShortFormat {
-    fn format(&self, config : &EntryConfig, entry : &Entry) -> String {
-        let name = short_name(entry);
-        let width = config.width - 2;
-        format!("{icon}{color}{name:<width$}{reset}",
-                name = name,
-                icon = entry.attr.icon,
-                color = color::Fg(color_for(config, &entry.attr.color)),
-                reset = color::Fg(color::Reset),
-                width = width,
-        )
+    fn name(&self) -> &'static str {
+        "short"
+    }
+
+    fn format(&self, config : &EntryConfig, entry : &Entry, width : usize) -> Cell {
+        let mut cell = Cell::new();
+        format_prefix(config, entry, false, &mut cell);
+        format_icon(config, entry, false, &mut cell);
+        format_name(config, entry, short_name(config, entry), &mut cell);
+        cell.pad_to(width.saturating_sub(1));
+        cell
+    }
+
+    fn predict(&self, config : &EntryConfig, entry : &Entry) -> usize {
+        self.format(config, entry, 0).width() + 2
+    }
+}
+
+#[cfg(test)]
+mod get_attr_tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    use self::super::super::testing::TempDir;
+
+    fn config() -> EntryConfig {
+        let mut files = Options::new();
+        files.insert("file".to_string(), "F".to_string());
+        files.insert("exec".to_string(), "X".to_string());
+        files.insert("empty".to_string(), "E".to_string());
+        files.insert("rs".to_string(), "R".to_string());
+        let mut folders = Options::new();
+        folders.insert("folder".to_string(), "D".to_string());
+        folders.insert("empty".to_string(), "O".to_string());
+        EntryConfig { files, folders, ..EntryConfig::default() }
+    }
+
+    fn touch(dir : &TempDir, name : &str, mode : u32) -> path::PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, "#!/bin/sh").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+        path
+    }
+
+    fn subdir(dir : &TempDir, name : &str) -> path::PathBuf {
+        let path = dir.join(name);
+        fs::create_dir(&path).unwrap();
+        path
+    }
+
+    #[test]
+    fn for_plain_file() {
+        let dir = TempDir::new("get_attr");
+        let path = touch(&dir, "plain", 0o644);
+        assert_eq!(Attr { icon: "F".to_string(), color: ColorType::UnrecognizedFile, recognized: false }, get_attr(&config(), &Entry::new(path, None)))
+    }
+
+    #[test]
+    fn for_executable_file() {
+        let dir = TempDir::new("get_attr");
+        let path = touch(&dir, "script", 0o755);
+        assert_eq!(Attr { icon: "X".to_string(), color: ColorType::Exec, recognized: false }, get_attr(&config(), &Entry::new(path, None)))
+    }
+
+    #[test]
+    fn when_symlink_points_to_executable() {
+        let dir = TempDir::new("get_attr");
+        touch(&dir, "script", 0o755);
+        fs::File::create(dir.join("empty")).unwrap();
+        symlink("script", dir.join("script_link")).unwrap();
+        symlink("empty", dir.join("empty_link")).unwrap();
+        let plain = Attr { icon: "F".to_string(), color: ColorType::UnrecognizedFile, recognized: false };
+        assert_eq!(plain, get_attr(&config(), &Entry::new(dir.join("script_link"), None)));
+        assert_eq!(plain, get_attr(&config(), &Entry::new(dir.join("empty_link"), None)))
+    }
+
+    #[test]
+    fn for_executable_file_with_known_extension() {
+        let dir = TempDir::new("get_attr");
+        let path = touch(&dir, "build.rs", 0o700);
+        assert_eq!(Attr { icon: "R".to_string(), color: ColorType::Exec, recognized: true }, get_attr(&config(), &Entry::new(path, None)))
+    }
+
+    #[test]
+    fn when_extension_is_a_special_icon_name() {
+        let dir = TempDir::new("get_attr");
+        let path = touch(&dir, "notes.exec", 0o644);
+        assert_eq!(Attr { icon: "F".to_string(), color: ColorType::UnrecognizedFile, recognized: false }, get_attr(&config(), &Entry::new(path, None)));
+        let path = touch(&dir, "notes.empty", 0o644);
+        assert_eq!(Attr { icon: "F".to_string(), color: ColorType::UnrecognizedFile, recognized: false }, get_attr(&config(), &Entry::new(path, None)))
+    }
+
+    #[test]
+    fn for_empty_file() {
+        let dir = TempDir::new("get_attr");
+        let path = dir.join("empty");
+        fs::File::create(&path).unwrap();
+        assert_eq!(Attr { icon: "E".to_string(), color: ColorType::Empty, recognized: false }, get_attr(&config(), &Entry::new(path, None)))
+    }
+
+    #[test]
+    fn for_directory() {
+        let dir = TempDir::new("get_attr");
+        let path = subdir(&dir, "full");
+        fs::write(path.join("file"), "").unwrap();
+        assert_eq!(Attr { icon: "D".to_string(), color: ColorType::Dir, recognized: false }, get_attr(&config(), &Entry::new(path, None)))
+    }
+
+    #[test]
+    fn when_directory_name_is_a_special_icon_name() {
+        let dir = TempDir::new("get_attr");
+        let path = subdir(&dir, "empty");
+        fs::write(path.join("file"), "").unwrap();
+        assert_eq!(Attr { icon: "D".to_string(), color: ColorType::Dir, recognized: false }, get_attr(&config(), &Entry::new(path, None)))
+    }
+
+    #[test]
+    fn for_empty_directory() {
+        let dir = TempDir::new("get_attr");
+        let path = subdir(&dir, "void");
+        assert_eq!(Attr { icon: "O".to_string(), color: ColorType::Empty, recognized: false }, get_attr(&config(), &Entry::new(path, None)))
     }
 
-    fn predict(&self, entry : &Entry) -> usize {
-        strlen(&short_name(entry)) + 3
+    #[test]
+    fn when_empty_directory_check_is_skipped() {
+        let dir = TempDir::new("get_attr");
+        let config = EntryConfig { check_empty_dirs: false, ..config() };
+        assert_eq!(ColorType::Dir, get_attr(&config, &Entry::new(subdir(&dir, "skipped"), None)).color)
     }
 }
 
-// NOTE: Colors DO count to length. Sadly.
-fn strlen(s : &String) -> usize {
-    s.graphemes(true).count() as usize
+#[cfg(test)]
+mod predict_tests {
+    use super::*;
+
+    fn config(icon_theme : IconTheme) -> EntryConfig {
+        EntryConfig { icon_theme, ..EntryConfig::default() }
+    }
+
+    fn entry(name : &str, indicator : &'static str) -> Entry {
+        let attr = Attr { icon: "I".to_string(), color: ColorType::Dir, recognized: false };
+        Entry { attr, indicator, ..Entry::new(path::PathBuf::from(name), None) }
+    }
+
+    #[test]
+    fn for_simple_case() {
+        assert_eq!(6, ShortFormat.predict(&config(IconTheme::Nerd), &entry("src", "")))
+    }
+
+    #[test]
+    fn when_entry_has_indicator() {
+        assert_eq!(7, ShortFormat.predict(&config(IconTheme::Nerd), &entry("src", "/")))
+    }
+
+    #[test]
+    fn when_name_is_quoted() {
+        let config = EntryConfig { quoting_style: QuotingStyle::ShellEscape, ..config(IconTheme::Nerd) };
+        assert_eq!(14, ShortFormat.predict(&config, &entry("a\nb", "")))
+    }
+
+    #[test]
+    fn when_prefixes_are_shown() {
+        let columns = ColumnWidths { inode: 7, blocks: 3, ..ColumnWidths::default() };
+        let config = EntryConfig { show_inode: true, show_blocks: true, columns, ..config(IconTheme::Nerd) };
+        assert_eq!(18, ShortFormat.predict(&config, &entry("src", "")))
+    }
+
+    #[test]
+    fn when_context_is_shown() {
+        let columns = ColumnWidths { context: 30, ..ColumnWidths::default() };
+        let config = EntryConfig { show_context: true, columns, ..config(IconTheme::Nerd) };
+        assert_eq!(37, ShortFormat.predict(&config, &entry("src", "")))
+    }
+
+    #[test]
+    fn when_formatted_to_predicted_width() {
+        let config = config(IconTheme::Nerd);
+        let entry = entry("src", "/");
+        let width = ShortFormat.predict(&config, &entry);
+        assert_eq!(width - 1, ShortFormat.format(&config, &entry, width).width());
+        assert_eq!(width + 3, ShortFormat.format(&config, &entry, width + 4).width())
+    }
+
+    #[test]
+    fn when_icons_are_wide() {
+        assert_eq!(8, ShortFormat.predict(&config(IconTheme::Emoji), &entry("src", "")));
+        assert_eq!(5, ShortFormat.predict(&config(IconTheme::None), &entry("src", "")))
+    }
+}
+
+#[cfg(test)]
+mod minimum_widths_tests {
+    use super::*;
+
+    fn config(style : TimeStyle, units : SizeUnits) -> EntryConfig {
+        let time_format = TimeFormat { style, ..TimeFormat::default() };
+        EntryConfig { time_format, size_format: SizeFormat { units, precision: None }, ..EntryConfig::default() }
+    }
+
+    #[test]
+    fn for_simple_case() {
+        let widths = minimum_widths(&config(TimeStyle::LongIso, SizeUnits::Iec));
+        assert_eq!(ColumnWidths { mode: 10, size: 5, time: 16, ..ColumnWidths::default() }, widths)
+    }
+
+    #[test]
+    fn when_time_style_changes_with_age() {
+        assert_eq!(12, minimum_widths(&config(TimeStyle::Locale, SizeUnits::Iec)).time);
+        assert_eq!(11, minimum_widths(&config(TimeStyle::Iso, SizeUnits::Iec)).time)
+    }
+
+    #[test]
+    fn when_columns_are_unbounded() {
+        let widths = minimum_widths(&config(TimeStyle::Relative, SizeUnits::Bytes));
+        assert_eq!(0, widths.size);
+        assert_eq!(0, widths.time)
+    }
+
+    #[test]
+    fn when_xattr_is_shown() {
+        let config = EntryConfig { show_xattr: true, ..config(TimeStyle::LongIso, SizeUnits::Iec) };
+        assert_eq!(10, minimum_widths(&config).mode)
+    }
+}
+
+fn strip_escapes(s : &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            out.push(c);
+            continue
+        }
+        match chars.next() {
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('\x40'..='\x7e').contains(&c) { break }
+                }
+            },
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' { break }
+                    if c == '\x1b' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+    out
+}
+
+fn strlen(s : &str) -> usize {
+    strip_escapes(s).graphemes(true).count()
 }
 
 #[cfg(test)]
 mod strlen_tests {
     use super::*;
+    use self::super::super::hyperlink::hyperlink;
     #[test]
     fn for_normal_string() {
-        assert_eq!(6, strlen(&".local".to_string()))
+        assert_eq!(6, strlen(".local"))
     }
 
     #[test]
     fn for_string_with_icons() {
-        assert_eq!(7, strlen(&".local".to_string()))
+        assert_eq!(7, strlen(".local"))
     }
 
     #[test]
     fn for_string_with_weird_stuff() {
-        assert_eq!(7, strlen(&"a̐.local".to_string()))
+        assert_eq!(7, strlen("a̐.local"))
     }
 
     #[test]
@@ -193,6 +741,16 @@ mod strlen_tests {
 
     #[test]
     fn for_string_with_color() {
-        assert_eq!(20, strlen(&format!("{color}.local{reset}", color = color::Fg(color::Red), reset = color::Fg(color::Reset))))
+        assert_eq!(6, strlen(&format!("{color}.local{reset}", color = color::Fg(color::Red), reset = color::Fg(color::Reset))))
+    }
+
+    #[test]
+    fn for_string_with_hyperlink() {
+        assert_eq!(6, strlen(&hyperlink("file://box/home/.local", ".local")))
+    }
+
+    #[test]
+    fn for_string_with_bell_terminated_hyperlink() {
+        assert_eq!(6, strlen("\x1b]8;;file:///.local\x07.local\x1b]8;;\x07"))
     }
 }
@@ -0,0 +1,55 @@
+use std::os::unix::ffi::OsStrExt;
+use std::path;
+
+use libc;
+
+pub fn hostname() -> String {
+    let mut buf = vec![0u8; 256];
+    let ret = unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len()) };
+    if ret != 0 {
+        return String::new()
+    }
+    let len = buf.iter().position(|b| *b == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..len]).into_owned()
+}
+
+fn percent_encode(bytes : &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len());
+    for b in bytes {
+        match *b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => out.push(*b as char),
+            b => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
+// NOTE: Links point at the entry itself, so symlinks are deliberately not resolved.
+pub fn file_url(host : &str, current_dir : &path::Path, path : &path::Path) -> String {
+    let absolute = current_dir.join(path);
+    format!("file://{}{}", percent_encode(host.as_bytes()), percent_encode(absolute.as_os_str().as_bytes()))
+}
+
+pub fn hyperlink(url : &str, text : &str) -> String {
+    format!("\x1b]8;;{url}\x1b\\{text}\x1b]8;;\x1b\\", url = url, text = text)
+}
+
+#[cfg(test)]
+mod file_url_tests {
+    use super::*;
+
+    #[test]
+    fn for_simple_case() {
+        assert_eq!("file://box/home/user/main.rs", file_url("box", path::Path::new("/tmp"), path::Path::new("/home/user/main.rs")))
+    }
+
+    #[test]
+    fn when_path_needs_encoding() {
+        assert_eq!("file://box/tmp/a%20b%25%0A", file_url("box", path::Path::new("/"), path::Path::new("/tmp/a b%\n")))
+    }
+
+    #[test]
+    fn when_path_is_relative() {
+        assert_eq!("file:///home/user/main.rs", file_url("", path::Path::new("/home/user"), path::Path::new("main.rs")))
+    }
+}
@@ -0,0 +1,126 @@
+use serde_yaml;
+
+use self::super::formatter::Options;
+
+#[derive(Hash, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum IconTheme {
+    Nerd,
+    Emoji,
+    Ascii,
+    None,
+}
+
+fn parse(yaml : &str) -> Options {
+    serde_yaml::from_str(yaml).unwrap()
+}
+
+fn blank(icons : Options) -> Options {
+    icons.into_keys().map(|name| (name, String::new())).collect()
+}
+
+impl IconTheme {
+    pub fn from_name(name : &str) -> Option<IconTheme> {
+        match name {
+            "nerd" => Some(IconTheme::Nerd),
+            "emoji" => Some(IconTheme::Emoji),
+            "ascii" => Some(IconTheme::Ascii),
+            "none" => Some(IconTheme::None),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            IconTheme::Nerd => "nerd",
+            IconTheme::Emoji => "emoji",
+            IconTheme::Ascii => "ascii",
+            IconTheme::None => "none",
+        }
+    }
+
+    pub fn files_source(&self) -> (&'static str, &'static str) {
+        match *self {
+            IconTheme::Nerd | IconTheme::None => ("default_config/files.yaml", include_str!("default_config/files.yaml")),
+            IconTheme::Emoji => ("default_config/emoji_files.yaml", include_str!("default_config/emoji_files.yaml")),
+            IconTheme::Ascii => ("default_config/ascii_files.yaml", include_str!("default_config/ascii_files.yaml")),
+        }
+    }
+
+    pub fn folders_source(&self) -> (&'static str, &'static str) {
+        match *self {
+            IconTheme::Nerd | IconTheme::None => ("default_config/folders.yaml", include_str!("default_config/folders.yaml")),
+            IconTheme::Emoji => ("default_config/emoji_folders.yaml", include_str!("default_config/emoji_folders.yaml")),
+            IconTheme::Ascii => ("default_config/ascii_folders.yaml", include_str!("default_config/ascii_folders.yaml")),
+        }
+    }
+
+    fn icons(&self, source : (&'static str, &'static str)) -> Options {
+        match *self {
+            IconTheme::None => blank(parse(source.1)),
+            _ => parse(source.1),
+        }
+    }
+
+    pub fn files(&self) -> Options {
+        self.icons(self.files_source())
+    }
+
+    pub fn folders(&self) -> Options {
+        self.icons(self.folders_source())
+    }
+
+    pub fn icon_width(&self) -> usize {
+        match *self {
+            IconTheme::Nerd | IconTheme::Ascii => 1,
+            IconTheme::Emoji => 2,
+            IconTheme::None => 0,
+        }
+    }
+
+    // NOTE: Short format historically prints Nerd Font glyphs right before the name.
+    pub fn gap(&self, long : bool) -> &'static str {
+        match *self {
+            IconTheme::None => "",
+            IconTheme::Nerd if !long => "",
+            _ => " ",
+        }
+    }
+}
+
+#[cfg(test)]
+mod icon_theme_tests {
+    use super::*;
+
+    fn keys(icons : &Options) -> Vec<String> {
+        let mut keys : Vec<String> = icons.keys().cloned().collect();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn for_complete_file_mappings() {
+        let nerd = keys(&IconTheme::Nerd.files());
+        for theme in &[IconTheme::Emoji, IconTheme::Ascii, IconTheme::None] {
+            assert_eq!(nerd, keys(&theme.files()), "{:?}", theme)
+        }
+    }
+
+    #[test]
+    fn for_complete_folder_mappings() {
+        let nerd = keys(&IconTheme::Nerd.folders());
+        for theme in &[IconTheme::Emoji, IconTheme::Ascii, IconTheme::None] {
+            assert_eq!(nerd, keys(&theme.folders()), "{:?}", theme)
+        }
+    }
+
+    #[test]
+    fn for_aliases_targets() {
+        let aliases : Options = parse(include_str!("default_config/file_aliases.yaml"));
+        for theme in &[IconTheme::Nerd, IconTheme::Emoji, IconTheme::Ascii, IconTheme::None] {
+            let files = theme.files();
+            for (alias, name) in &aliases {
+                assert!(files.contains_key(name), "{:?}: {} -> {}", theme, alias, name)
+            }
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use std::fs;
+use std::os::unix::fs::{FileTypeExt, PermissionsExt};
+
+#[derive(Hash, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum IndicatorStyle {
+    None,
+    Slash,
+    FileType,
+    Classify,
+}
+
+impl IndicatorStyle {
+    pub fn from_name(name : &str) -> Option<IndicatorStyle> {
+        match name {
+            "none" => Some(IndicatorStyle::None),
+            "slash" => Some(IndicatorStyle::Slash),
+            "file-type" => Some(IndicatorStyle::FileType),
+            "classify" => Some(IndicatorStyle::Classify),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            IndicatorStyle::None => "none",
+            IndicatorStyle::Slash => "slash",
+            IndicatorStyle::FileType => "file-type",
+            IndicatorStyle::Classify => "classify",
+        }
+    }
+
+    // NOTE: Expects `lstat` metadata, so symlinks get `@` rather than their target's indicator.
+    pub fn indicator(&self, metadata : &fs::Metadata) -> &'static str {
+        let file_type = metadata.file_type();
+        match *self {
+            IndicatorStyle::None => "",
+            _ if file_type.is_dir() => "/",
+            IndicatorStyle::Slash => "",
+            _ if file_type.is_symlink() => "@",
+            _ if file_type.is_fifo() => "|",
+            _ if file_type.is_socket() => "=",
+            IndicatorStyle::Classify if file_type.is_file() && metadata.permissions().mode() & 0o111 != 0 => "*",
+            _ => "",
+        }
+    }
+}
+
+#[cfg(test)]
+mod indicator_tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    use self::super::super::testing::TempDir;
+
+    fn executable(dir : &TempDir) -> fs::Metadata {
+        let path = dir.join("script");
+        fs::write(&path, "").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        fs::symlink_metadata(&path).unwrap()
+    }
+
+    fn link(dir : &TempDir) -> fs::Metadata {
+        let path = dir.join("link");
+        symlink("script", &path).unwrap();
+        fs::symlink_metadata(&path).unwrap()
+    }
+
+    #[test]
+    fn for_classify() {
+        let dir = TempDir::new("indicator");
+        assert_eq!("/", IndicatorStyle::Classify.indicator(&fs::symlink_metadata(dir.path()).unwrap()));
+        assert_eq!("*", IndicatorStyle::Classify.indicator(&executable(&dir)));
+        assert_eq!("@", IndicatorStyle::Classify.indicator(&link(&dir)))
+    }
+
+    #[test]
+    fn for_file_type() {
+        let dir = TempDir::new("indicator");
+        assert_eq!("", IndicatorStyle::FileType.indicator(&executable(&dir)));
+        assert_eq!("@", IndicatorStyle::FileType.indicator(&link(&dir)))
+    }
+
+    #[test]
+    fn for_slash() {
+        let dir = TempDir::new("indicator");
+        assert_eq!("/", IndicatorStyle::Slash.indicator(&fs::symlink_metadata(dir.path()).unwrap()));
+        assert_eq!("", IndicatorStyle::Slash.indicator(&link(&dir)))
+    }
+
+    #[test]
+    fn for_none() {
+        let dir = TempDir::new("indicator");
+        assert_eq!("", IndicatorStyle::None.indicator(&fs::symlink_metadata(dir.path()).unwrap()))
+    }
+}
@@ -1,17 +1,61 @@
 extern crate termion;
 extern crate serde;
+extern crate serde_yaml;
 extern crate unicode_segmentation;
 extern crate num_iter;
+extern crate rayon;
+extern crate libc;
 
 use std::path;
 use std::fs;
+use std::io::{self, Write};
+use std::slice;
 
 mod colors;
 mod formatter;
-use self::formatter::{Entry,get_attr};
-pub use self::formatter::{Formatter,EntryConfig,ShortFormat,LongFormat};
+use self::formatter::{Entry,get_attr,column_widths,minimum_widths};
+pub use self::formatter::{Formatter,EntryConfig,ColumnWidths,Options,ShortFormat,LongFormat};
+mod cell;
+pub use self::cell::{Cell,Span};
+mod render;
+pub use self::render::{Renderer,AnsiRenderer,PlainRenderer,HtmlRenderer};
 mod tabulator;
-pub use self::tabulator::{Tabulator,Config,PlanningTabulator,NaiveTabulator,BinsearchTabulator,EntryOrder};
+pub use self::tabulator::{Tabulator,Config,PlanningTabulator,NaiveTabulator,BinsearchTabulator,OptimalTabulator,SingleColumnTabulator,EntryOrder};
+mod report;
+pub use self::report::{Report,ReportStyle};
+mod du;
+use self::du::DiskUsage;
+mod size;
+pub use self::size::{SizeFormat,SizeUnits,parse_block_size};
+mod time;
+pub use self::time::{TimeFormat,TimeField,TimeStyle,parse_time_style};
+mod icons;
+pub use self::icons::IconTheme;
+mod indicator;
+pub use self::indicator::IndicatorStyle;
+mod quoting;
+pub use self::quoting::QuotingStyle;
+mod hyperlink;
+pub use self::hyperlink::hostname;
+mod permissions;
+mod xattr;
+use self::xattr::{mode_suffix,selinux_context};
+mod settings;
+pub use self::settings::{settings_path,load_settings};
+mod dump;
+pub use self::dump::{ConfigDump,DumpFormat,Origin,Origins,Setting,Value};
+mod man;
+pub use self::man::man_page;
+mod showcase;
+pub use self::showcase::show_theme;
+mod aliases;
+pub use self::aliases::{AliasError,resolve_alias,resolve_aliases};
+mod check;
+pub use self::check::{ConfigFile,ConfigFiles,Problem,check_config,check_settings};
+#[cfg(test)]
+mod testing;
+
+use rayon::prelude::*;
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Verbosity {
@@ -20,37 +64,311 @@ pub enum Verbosity {
     Debug,
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SortOrder {
+    Name,
+    Size,
+    None,
+}
+
+impl SortOrder {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            SortOrder::Name => "name",
+            SortOrder::Size => "size",
+            SortOrder::None => "none",
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Action {
     pub verbosity: Verbosity,
     pub directory: path::PathBuf,
     pub config: Config,
-    pub tabulator: Box<Tabulator>,
+    pub tabulator: Box<dyn Tabulator>,
+    pub renderer: Box<dyn Renderer>,
+    pub report: Option<ReportStyle>,
+    pub sort: SortOrder,
+    pub du: bool,
+    pub one_file_system: bool,
+    pub indicator_style: IndicatorStyle,
+    pub parallel_threshold: usize,
+    pub stream: bool,
 }
 
-pub fn run(action : Action) {
-    if action.verbosity != Verbosity::Quiet {
-        println!("Looking at {}", action.directory.display());
+pub const DEFAULT_PARALLEL_THRESHOLD : usize = 512;
+
+pub fn run(mut action : Action) {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let _ = write_listing(&mut action, &mut out);
+}
+
+fn write_listing(action : &mut Action, out : &mut dyn Write) -> io::Result<()> {
+    let mut report = Report::default();
+    let disk_usage = DiskUsage::new(action.one_file_system);
+    let directory = action.directory.clone();
+    write!(out, "{}", action.renderer.begin())?;
+    if action.stream {
+        stream(action, &directory, &disk_usage, &mut report, out)?;
+    } else {
+        list(action, &directory, &disk_usage, &mut report, out)?;
+    }
+    if let Some(style) = action.report {
+        writeln!(out, "{}", action.renderer.render(&action.config.entry, &report.format(style)))?;
+    }
+    write!(out, "{}", action.renderer.end())
+}
+
+fn write_message(action : &Action, out : &mut dyn Write, text : String) -> io::Result<()> {
+    let mut cell = Cell::new();
+    cell.text(text);
+    writeln!(out, "{}", action.renderer.render(&action.config.entry, &cell))
+}
 
+fn load_entry(config : &EntryConfig, indicator_style : IndicatorStyle, path : path::PathBuf, file_type : Option<fs::FileType>) -> Entry {
+    let mut entry = Entry::new(path, file_type);
+    if indicator_style != IndicatorStyle::None {
+        entry.indicator = entry.lstat().map(|metadata| indicator_style.indicator(metadata)).unwrap_or("");
     }
-    let dirs = fs::read_dir(action.directory).unwrap();
-    let config = action.config;
-    let mut ls : Vec<Entry> = dirs.map(|dir| {
-        let path = dir.unwrap().path();
-        Entry { path: path.clone(), attr: get_attr(&config.entry, &path) }
+    if config.show_xattr {
+        entry.xattr = mode_suffix(&entry.path);
+    }
+    if config.show_context {
+        entry.context = selinux_context(&entry.path);
+    }
+    entry.attr = get_attr(config, &entry);
+    entry
+}
+
+fn load_entries(action : &Action, directory : &path::Path, disk_usage : &DiskUsage) -> Vec<Entry> {
+    let dirs : Vec<(path::PathBuf, Option<fs::FileType>)> = fs::read_dir(directory).unwrap().map(|dir| {
+        let dir = dir.unwrap();
+        (dir.path(), dir.file_type().ok())
     }).collect();
+    let config = &action.config.entry;
+    let indicator_style = action.indicator_style;
+    let mut ls : Vec<Entry> = if dirs.len() >= action.parallel_threshold {
+        dirs.into_par_iter().map(|(path, file_type)| {
+            let entry = load_entry(config, indicator_style, path, file_type);
+            entry.lstat();
+            entry
+        }).collect()
+    } else {
+        dirs.into_iter().map(|(path, file_type)| load_entry(config, indicator_style, path, file_type)).collect()
+    };
+    // NOTE: One directory at a time in read_dir order, so which one a shared hard link counts for does not depend on scheduling.
+    if action.du {
+        for entry in ls.iter_mut().filter(|entry| entry.is_dir()) {
+            let size = entry.lstat().map(|metadata| disk_usage.measure(&entry.path, metadata));
+            entry.disk_usage = size;
+        }
+    }
+    ls
+}
+
+fn list(action : &mut Action, directory : &path::Path, disk_usage : &DiskUsage, report : &mut Report, out : &mut dyn Write) -> io::Result<()> {
+    if action.verbosity != Verbosity::Quiet {
+        write_message(action, out, format!("Looking at {}", directory.display()))?;
+    }
+    let mut ls = load_entries(action, directory, disk_usage);
     if ls.is_empty() {
         if action.verbosity != Verbosity::Quiet {
-            println!("is empty.");
+            write_message(action, out, "is empty.".to_string())?;
+        }
+        return Ok(())
+    }
+    if action.report.is_some() {
+        for entry in &ls {
+            report.add(entry);
         }
-        return
     }
-    ls.sort_unstable();
-    let rows = action.tabulator.tabulate(&config, ls);
+    match action.sort {
+        SortOrder::Name => ls.sort_unstable(),
+        SortOrder::Size => ls.sort_unstable_by(|a, b| b.size().cmp(&a.size()).then_with(|| a.cmp(b))),
+        SortOrder::None => {},
+    }
+    action.config.entry.columns = column_widths(&action.config.entry, &ls);
+    let rows = action.tabulator.tabulate(&action.config, ls);
     for items in rows {
         for item in items {
-            print!("{}", item);
+            write!(out, "{}", action.renderer.render(&action.config.entry, &item))?;
         }
-        println!("");
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+fn stream(action : &mut Action, directory : &path::Path, disk_usage : &DiskUsage, report : &mut Report, out : &mut dyn Write) -> io::Result<()> {
+    if action.verbosity != Verbosity::Quiet {
+        write_message(action, out, format!("Looking at {}", directory.display()))?;
+    }
+    action.config.entry.columns = minimum_widths(&action.config.entry);
+    let mut empty = true;
+    for dir in fs::read_dir(directory).unwrap() {
+        let dir = dir.unwrap();
+        let mut entry = load_entry(&action.config.entry, action.indicator_style, dir.path(), dir.file_type().ok());
+        if action.du && entry.is_dir() {
+            let size = entry.lstat().map(|metadata| disk_usage.measure(&entry.path, metadata));
+            entry.disk_usage = size;
+        }
+        if action.report.is_some() {
+            report.add(&entry);
+        }
+        empty = false;
+        let columns = column_widths(&action.config.entry, slice::from_ref(&entry));
+        action.config.entry.columns.widen(&columns);
+        let cell = action.config.formatter.format(&action.config.entry, &entry, 0);
+        writeln!(out, "{}", action.renderer.render(&action.config.entry, &cell))?;
+    }
+    if empty && action.verbosity != Verbosity::Quiet {
+        write_message(action, out, "is empty.".to_string())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod write_listing_tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use self::testing::TempDir;
+    use std::os::unix::fs::symlink;
+    use self::formatter::{Attr, METADATA_CALLS};
+
+    fn action(dir : &TempDir, formatter : Box<dyn Formatter>, sort : SortOrder, stream : bool) -> Action {
+        let mut files = Options::new();
+        files.insert("file".to_string(), "F".to_string());
+        let mut folders = Options::new();
+        folders.insert("folder".to_string(), "D".to_string());
+        let time_format = TimeFormat { style: TimeStyle::LongIso, ..TimeFormat::default() };
+        let size_format = SizeFormat { units: SizeUnits::Iec, precision: None };
+        let entry = EntryConfig { files, folders, time_format, size_format, ..EntryConfig::default() };
+        Action {
+            verbosity: Verbosity::Quiet,
+            directory: dir.path().to_path_buf(),
+            config: Config { entry, max_width: 80, formatter, entry_order: EntryOrder::Vertical },
+            tabulator: Box::new(SingleColumnTabulator),
+            renderer: Box::new(PlainRenderer),
+            report: None,
+            sort,
+            du: false,
+            one_file_system: false,
+            indicator_style: IndicatorStyle::None,
+            parallel_threshold: DEFAULT_PARALLEL_THRESHOLD,
+            stream,
+        }
+    }
+
+    fn raw_output(mut action : Action) -> String {
+        let mut out = Vec::new();
+        write_listing(&mut action, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn output(action : Action) -> Vec<String> {
+        raw_output(action).lines().map(|line| line.trim_end().to_string()).collect()
+    }
+
+    fn populate(dir : &TempDir) {
+        fs::write(dir.join("b"), "x").unwrap();
+        fs::write(dir.join("a"), vec![0; 200_000]).unwrap();
+        fs::write(dir.join("c"), "").unwrap();
+    }
+
+    #[test]
+    fn for_simple_case() {
+        let dir = TempDir::new("listing_simple");
+        populate(&dir);
+        assert_eq!(vec!["Fa", "Fb", "Fc"], output(action(&dir, Box::new(ShortFormat), SortOrder::Name, false)))
+    }
+
+    #[test]
+    fn when_unsorted() {
+        let dir = TempDir::new("listing_unsorted");
+        populate(&dir);
+        let lines = output(action(&dir, Box::new(ShortFormat), SortOrder::None, false));
+        let order : Vec<String> = fs::read_dir(dir.path()).unwrap().map(|dir| format!("F{}", dir.unwrap().file_name().to_string_lossy())).collect();
+        assert_eq!(order, lines)
+    }
+
+    #[test]
+    fn when_streamed() {
+        let dir = TempDir::new("listing_streamed");
+        populate(&dir);
+        let listed = output(action(&dir, Box::new(ShortFormat), SortOrder::None, false));
+        assert_eq!(listed, output(action(&dir, Box::new(ShortFormat), SortOrder::None, true)))
+    }
+
+    #[test]
+    fn when_streamed_in_long_format() {
+        let dir = TempDir::new("listing_streamed_long");
+        populate(&dir);
+        // NOTE: One size at the widest a scaled size can be, so `-l` needs the same width `-lU` starts from.
+        fs::write(dir.join("d"), vec![0; 1023 * 1024]).unwrap();
+        let listed = raw_output(action(&dir, Box::new(LongFormat), SortOrder::None, false));
+        assert_eq!(listed, raw_output(action(&dir, Box::new(LongFormat), SortOrder::None, true)))
+    }
+
+    #[test]
+    fn when_loaded_in_parallel() {
+        let dir = TempDir::new("listing_parallel");
+        populate(&dir);
+        fs::create_dir(dir.join("d")).unwrap();
+        fs::set_permissions(dir.join("b"), fs::Permissions::from_mode(0o755)).unwrap();
+        let load = |parallel_threshold : usize| -> Vec<(path::PathBuf, Attr, &'static str, Option<u64>)> {
+            let action = Action {
+                indicator_style: IndicatorStyle::Classify,
+                du: true,
+                parallel_threshold,
+                ..action(&dir, Box::new(ShortFormat), SortOrder::None, false)
+            };
+            load_entries(&action, dir.path(), &DiskUsage::new(false)).into_iter().map(|entry| (entry.path, entry.attr, entry.indicator, entry.disk_usage)).collect()
+        };
+        let serial = load(usize::MAX);
+        assert_eq!(4, serial.len());
+        assert_eq!(serial, load(0))
+    }
+
+    #[test]
+    fn when_metadata_is_read_once_per_entry() {
+        let dir = TempDir::new("listing_stat_once");
+        populate(&dir);
+        fs::create_dir(dir.join("d")).unwrap();
+        symlink("b", dir.join("link")).unwrap();
+        let action = Action {
+            indicator_style: IndicatorStyle::Classify,
+            du: true,
+            report: Some(ReportStyle::Long),
+            parallel_threshold: usize::MAX,
+            ..action(&dir, Box::new(LongFormat), SortOrder::Size, false)
+        };
+        METADATA_CALLS.with(|calls| calls.set(0));
+        output(action);
+        // NOTE: One lstat for each of the five entries, plus one stat for the symlink.
+        assert_eq!(6, METADATA_CALLS.with(|calls| calls.get()))
+    }
+
+    #[test]
+    fn when_reporting_disk_usage() {
+        let dir = TempDir::new("listing_report_du");
+        populate(&dir);
+        fs::create_dir(dir.join("d")).unwrap();
+        fs::write(dir.join("d").join("e"), "hello").unwrap();
+        let directory_size = fs::symlink_metadata(dir.join("d")).unwrap().len();
+        let action = Action {
+            du: true,
+            report: Some(ReportStyle::Short),
+            ..action(&dir, Box::new(ShortFormat), SortOrder::Name, false)
+        };
+        let lines = output(action);
+        let total = format!("Total size: {} B", 200_001 + directory_size + 5);
+        assert!(lines.last().unwrap().ends_with(&total), "{:?}", lines)
+    }
+
+    #[test]
+    fn when_streamed_directory_is_empty() {
+        let dir = TempDir::new("listing_streamed_empty");
+        assert!(output(action(&dir, Box::new(ShortFormat), SortOrder::None, true)).is_empty())
     }
 }
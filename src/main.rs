@@ -1,25 +1,92 @@
 extern crate clap;
-use clap::{Arg, App};
+use clap::{Arg, App, Shell};
 
 extern crate termion;
-use termion::terminal_size;
+use termion::{terminal_size,is_tty};
 
 extern crate serde_yaml;
 
 use std::env;
+use std::fs;
+use std::io;
 use std::path;
+use std::process;
+use std::time::SystemTime;
 
 extern crate colorls;
 use colorls::*;
 
-fn main() {
-    let matches = App::new("ColorLs")
+const FILE_ALIASES : (&str, &str) = ("default_config/file_aliases.yaml", include_str!("default_config/file_aliases.yaml"));
+const FOLDER_ALIASES : (&str, &str) = ("default_config/folder_aliases.yaml", include_str!("default_config/folder_aliases.yaml"));
+const COLORS : (&str, &str) = ("default_config/dark_colors.yaml", include_str!("default_config/dark_colors.yaml"));
+
+fn run_check_config(icon_theme : Option<&str>) -> i32 {
+    let mut problems = vec![];
+    let settings = settings_path().and_then(|path| {
+        fs::read_to_string(&path).ok().map(|contents| check_settings(&ConfigFile::new(&path.display().to_string(), &contents), &mut problems))
+    }).unwrap_or_default();
+    let icon_theme = icon_theme.map(|name| name.to_string())
+        .or_else(|| settings.get("icon_theme").cloned())
+        .and_then(|name| IconTheme::from_name(&name))
+        .unwrap_or(IconTheme::Nerd);
+    let (files_name, files) = icon_theme.files_source();
+    let (folders_name, folders) = icon_theme.folders_source();
+    problems.extend(check_config(&ConfigFiles {
+        files: ConfigFile::new(files_name, files),
+        file_aliases: ConfigFile::new(FILE_ALIASES.0, FILE_ALIASES.1),
+        folders: ConfigFile::new(folders_name, folders),
+        folder_aliases: ConfigFile::new(FOLDER_ALIASES.0, FOLDER_ALIASES.1),
+        colors: ConfigFile::new(COLORS.0, COLORS.1),
+    }));
+    for problem in &problems {
+        eprintln!("{}", problem);
+    }
+    if problems.is_empty() {
+        println!("Configuration OK");
+        0
+    } else {
+        1
+    }
+}
+
+const SETTING_FLAGS : &[(&str, &[&str])] = &[
+    ("format", &["long"]),
+    ("tabulator", &["naive", "one per line", "long"]),
+    ("entry_order", &["entry order"]),
+    ("sort", &["sort", "sort by size", "unsorted"]),
+    ("stream", &["sort", "unsorted", "one per line", "long"]),
+    ("du", &["du"]),
+    ("one_file_system", &["one file system"]),
+    ("report", &["report"]),
+    ("icon_theme", &["icon theme"]),
+    ("indicator_style", &["indicator style", "classify", "file type", "slash"]),
+    ("quoting_style", &["quoting style", "escape", "hide control chars"]),
+    ("hyperlink", &["hyperlink"]),
+    ("output", &["output"]),
+    ("size_units", &["block size", "si", "human readable"]),
+    ("size_precision", &["size precision"]),
+    ("time_field", &["time"]),
+    ("time_style", &["time style"]),
+    ("show_inode", &["inode"]),
+    ("show_blocks", &["size"]),
+    ("show_links", &["links"]),
+    ("show_xattr", &["long"]),
+    ("show_context", &["context"]),
+    ("check_empty_dirs", &["no empty check"]),
+    ("parallel_threshold", &["parallel threshold"]),
+];
+
+fn app<'a, 'b>() -> App<'a, 'b> {
+    App::new("ColorLs")
         .version("0.1.2")
         .author("scoiatael <czapl.luk+git@gmail.com>")
         .about("List information about the FILEs (the current directory by default).")
         .arg(Arg::with_name("entry order")
              .short("x")
              .help("list entries by lines instead of by columns"))
+        .arg(Arg::with_name("one per line")
+             .short("1")
+             .help("Prints one entry per line"))
         .arg(Arg::with_name("long")
              .long("long")
              .short("l")
@@ -29,6 +96,159 @@ fn main() {
              .short("n")
              .multiple(true)
              .help("Prints using naive tabulator"))
+        .arg(Arg::with_name("sort")
+             .long("sort")
+             .takes_value(true)
+             .value_name("WORD")
+             .possible_values(&["name", "size", "none"])
+             .help("Sorts by WORD instead of name"))
+        .arg(Arg::with_name("sort by size")
+             .short("S")
+             .help("Sorts by file size, largest first"))
+        .arg(Arg::with_name("unsorted")
+             .short("U")
+             .help("Does not sort, lists entries in directory order (printed as they are read with -1 or -l, where inode, block, link and byte counts may widen their column partway)"))
+        .arg(Arg::with_name("du")
+             .long("du")
+             .help("Shows total recursive size of directories"))
+        .arg(Arg::with_name("one file system")
+             .long("one-file-system")
+             .requires("du")
+             .help("Skips directories on different file systems when computing --du sizes"))
+        .arg(Arg::with_name("human readable")
+             .short("h")
+             .long("human-readable")
+             .help("Prints sizes in powers of 1024 (e.g. 1.5K 234M 2G)"))
+        .arg(Arg::with_name("si")
+             .long("si")
+             .help("Prints sizes in powers of 1000 (e.g. 1.5K 234M 2G)"))
+        .arg(Arg::with_name("block size")
+             .long("block-size")
+             .takes_value(true)
+             .value_name("SIZE")
+             .validator(|value| parse_block_size(&value).map(|_| ()))
+             .help("Scales sizes by SIZE before printing them (e.g. K, M, KB, 4K, human-readable or si)"))
+        .arg(Arg::with_name("size precision")
+             .long("size-precision")
+             .takes_value(true)
+             .value_name("N")
+             .validator(|value| value.parse::<usize>().map(|_| ()).map_err(|e| e.to_string()))
+             .help("Prints scaled sizes with exactly N decimal places"))
+        .arg(Arg::with_name("time")
+             .long("time")
+             .takes_value(true)
+             .value_name("WORD")
+             .possible_values(&["mtime", "atime", "ctime", "birth"])
+             .help("Shows WORD timestamp in long format instead of modification time"))
+        .arg(Arg::with_name("time style")
+             .long("time-style")
+             .takes_value(true)
+             .value_name("STYLE")
+             .validator(|value| parse_time_style(&value).map(|_| ()))
+             .help("Prints timestamps using STYLE: locale, iso, long-iso, full-iso, relative or +FORMAT (like date)"))
+        .arg(Arg::with_name("icon theme")
+             .long("icon-theme")
+             .takes_value(true)
+             .value_name("THEME")
+             .possible_values(&["nerd", "emoji", "ascii", "none"])
+             .help("Prints icons from THEME (nerd needs a Nerd Font)"))
+        .arg(Arg::with_name("classify")
+             .short("F")
+             .long("classify")
+             .help("Appends indicator (one of */=@|) to entries"))
+        .arg(Arg::with_name("file type")
+             .long("file-type")
+             .help("Likewise, except do not append '*'"))
+        .arg(Arg::with_name("slash")
+             .short("p")
+             .help("Appends / indicator to directories"))
+        .arg(Arg::with_name("indicator style")
+             .long("indicator-style")
+             .takes_value(true)
+             .value_name("WORD")
+             .possible_values(&["none", "slash", "file-type", "classify"])
+             .help("Appends indicator with style WORD to entry names"))
+        .arg(Arg::with_name("quoting style")
+             .long("quoting-style")
+             .takes_value(true)
+             .value_name("WORD")
+             .possible_values(&["literal", "shell", "shell-escape", "c", "escape", "hide-control"])
+             .help("Prints entry names using quoting style WORD (shell-escape on a terminal, literal otherwise)"))
+        .arg(Arg::with_name("escape")
+             .short("b")
+             .long("escape")
+             .help("Prints C-style escapes for nongraphic characters"))
+        .arg(Arg::with_name("hide control chars")
+             .short("q")
+             .long("hide-control-chars")
+             .help("Prints ? instead of nongraphic characters"))
+        .arg(Arg::with_name("hyperlink")
+             .long("hyperlink")
+             .takes_value(true)
+             .value_name("WHEN")
+             .possible_values(&["auto", "always", "never"])
+             .help("Links entry names to their files (auto: only on a terminal)"))
+        .arg(Arg::with_name("output")
+             .long("output")
+             .takes_value(true)
+             .value_name("FORMAT")
+             .possible_values(&["ansi", "plain", "html"])
+             .help("Renders the listing as FORMAT: ansi (terminal colors), plain (no escapes) or html"))
+        .arg(Arg::with_name("inode")
+             .short("i")
+             .long("inode")
+             .help("Prints the index number of each entry"))
+        .arg(Arg::with_name("size")
+             .short("s")
+             .long("size")
+             .help("Prints the allocated size of each entry, in blocks"))
+        .arg(Arg::with_name("links")
+             .long("links")
+             .help("Prints the number of hard links of each entry (always shown in long format)"))
+        .arg(Arg::with_name("context")
+             .short("Z")
+             .long("context")
+             .help("Prints the SELinux security context of each entry"))
+        .arg(Arg::with_name("parallel threshold")
+             .long("parallel-threshold")
+             .takes_value(true)
+             .value_name("N")
+             .validator(|value| value.parse::<usize>().map(|_| ()).map_err(|e| e.to_string()))
+             .help("Reads metadata on all cores for directories with at least N entries (0: always)"))
+        .arg(Arg::with_name("no empty check")
+             .long("no-empty-check")
+             .help("Skips looking into directories to mark empty ones (e.g. on network mounts)"))
+        .arg(Arg::with_name("report")
+             .long("report")
+             .takes_value(true)
+             .value_name("STYLE")
+             .min_values(0)
+             .require_equals(true)
+             .possible_values(&["short", "long"])
+             .help("Prints a summary of listed entries (long by default)"))
+        .arg(Arg::with_name("check config")
+             .long("check-config")
+             .help("Validates the configuration files and exits (non-zero on problems)"))
+        .arg(Arg::with_name("dump config")
+             .long("dump-config")
+             .takes_value(true)
+             .value_name("FORMAT")
+             .min_values(0)
+             .require_equals(true)
+             .possible_values(&["yaml", "json"])
+             .help("Prints the effective configuration and where each value came from, then exits (yaml by default)"))
+        .arg(Arg::with_name("show theme")
+             .long("show-theme")
+             .help("Prints every color and icon of the current theme, then exits"))
+        .arg(Arg::with_name("completions")
+             .long("completions")
+             .takes_value(true)
+             .value_name("SHELL")
+             .possible_values(&Shell::variants())
+             .help("Prints a completion script for SHELL, then exits"))
+        .arg(Arg::with_name("man")
+             .long("man")
+             .help("Prints the manual page in roff format, then exits"))
         .arg(Arg::with_name("verbose")
              .short("v")
              .long("verbose")
@@ -37,36 +257,167 @@ fn main() {
         .arg(Arg::with_name("FILE")
              .required(false)
              .index(1))
-        .get_matches();
+}
+
+fn main() {
+    let matches = app().get_matches();
 
     let verbosity = match matches.occurrences_of("v") {
         0 => Verbosity::Quiet,
         1 => Verbosity::Warn,
-        2 | _ =>  Verbosity::Debug,
+        _ =>  Verbosity::Debug,
     };
-    let tabulator : Box<Tabulator> = match matches.occurrences_of("naive") {
-        0 => Box::new(BinsearchTabulator),
+    let tabulator : Box<dyn Tabulator> = match matches.occurrences_of("naive") {
+        _ if matches.is_present("one per line") || matches.is_present("long") => Box::new(SingleColumnTabulator),
+        0 => Box::new(OptimalTabulator),
         1 => Box::new(PlanningTabulator),
         _ => Box::new(NaiveTabulator),
     };
-    let formatter : Box<Formatter> = match matches.occurrences_of("long") {
+    let renderer : Box<dyn Renderer> = match matches.value_of("output") {
+        Some("plain") => Box::new(PlainRenderer),
+        Some("html") => Box::new(HtmlRenderer),
+        _ => Box::new(AnsiRenderer),
+    };
+    let formatter : Box<dyn Formatter> = match matches.occurrences_of("long") {
         0 => Box::new(ShortFormat),
-        1 | _ =>  Box::new(LongFormat),
+        _ =>  Box::new(LongFormat),
+    };
+    let report = if matches.is_present("report") {
+        match matches.value_of("report") {
+            Some("short") => Some(ReportStyle::Short),
+            _ => Some(ReportStyle::Long),
+        }
+    } else {
+        None
+    };
+    let size_units = if let Some(block_size) = matches.value_of("block size") {
+        parse_block_size(block_size).unwrap()
+    } else if matches.is_present("si") {
+        SizeUnits::Si
+    } else if matches.is_present("human readable") {
+        SizeUnits::Iec
+    } else {
+        SizeUnits::Bytes
+    };
+    let size_format = SizeFormat {
+        units: size_units,
+        precision: matches.value_of("size precision").map(|n| n.parse().unwrap()),
+    };
+    let time_format = TimeFormat {
+        field: match matches.value_of("time") {
+            Some("atime") => TimeField::Accessed,
+            Some("ctime") => TimeField::Changed,
+            Some("birth") => TimeField::Birth,
+            _ => TimeField::Modified,
+        },
+        style: matches.value_of("time style").map(|style| parse_time_style(style).unwrap()).unwrap_or(TimeStyle::Locale),
+    };
+    let indicator_style = if let Some(style) = matches.value_of("indicator style") {
+        IndicatorStyle::from_name(style).unwrap()
+    } else if matches.is_present("classify") {
+        IndicatorStyle::Classify
+    } else if matches.is_present("file type") {
+        IndicatorStyle::FileType
+    } else if matches.is_present("slash") {
+        IndicatorStyle::Slash
+    } else {
+        IndicatorStyle::None
+    };
+    let quoting_style = if let Some(style) = matches.value_of("quoting style") {
+        QuotingStyle::from_name(style).unwrap()
+    } else if matches.is_present("escape") {
+        QuotingStyle::Escape
+    } else if matches.is_present("hide control chars") {
+        QuotingStyle::HideControl
+    } else if is_tty(&io::stdout()) {
+        QuotingStyle::ShellEscape
+    } else {
+        QuotingStyle::Literal
+    };
+    let hyperlink = match matches.value_of("hyperlink") {
+        Some("always") => true,
+        Some("auto") => is_tty(&io::stdout()),
+        _ => false,
+    };
+    let sort = match matches.value_of("sort") {
+        Some("size") => SortOrder::Size,
+        Some("none") => SortOrder::None,
+        Some(_) => SortOrder::Name,
+        None if matches.is_present("sort by size") => SortOrder::Size,
+        None if matches.is_present("unsorted") => SortOrder::None,
+        None => SortOrder::Name,
     };
     let entry_order = match matches.occurrences_of("entry order") {
         0 => EntryOrder::Vertical,
-        1 | _ => EntryOrder::Horizontal,
+        _ => EntryOrder::Horizontal,
     };
 
-    let file_icons = serde_yaml::from_str(include_str!("default_config/files.yaml")).unwrap();
-    let folder_icons = serde_yaml::from_str(include_str!("default_config/folders.yaml")).unwrap();
-    let file_aliases = serde_yaml::from_str(include_str!("default_config/file_aliases.yaml")).unwrap();
-    let folder_aliases = serde_yaml::from_str(include_str!("default_config/folder_aliases.yaml")).unwrap();
-    let colors = serde_yaml::from_str(include_str!("default_config/dark_colors.yaml")).unwrap();
+    if let Some(shell) = matches.value_of("completions") {
+        app().gen_completions_to("colorls", shell.parse::<Shell>().unwrap(), &mut io::stdout());
+        return
+    }
+    if matches.is_present("man") {
+        let mut help = vec![];
+        app().set_term_width(0).template("{unified}").write_help(&mut help).unwrap();
+        print!("{}", man_page(env!("CARGO_PKG_VERSION"), &String::from_utf8_lossy(&help)));
+        return
+    }
+    if matches.is_present("check config") {
+        process::exit(run_check_config(matches.value_of("icon theme")));
+    }
+
+    let settings = match settings_path().map(|path| load_settings(&path)) {
+        Some(Ok(settings)) => settings,
+        Some(Err(message)) => {
+            eprintln!("{}", message);
+            process::exit(2)
+        },
+        None => Options::new(),
+    };
+    let mut origins = Origins::new();
+    for &(name, flags) in SETTING_FLAGS {
+        if flags.iter().any(|flag| matches.occurrences_of(flag) > 0) {
+            origins.insert(name, Origin::CommandLine);
+        }
+    }
+    if !origins.contains_key("icon_theme") && settings.contains_key("icon_theme") {
+        origins.insert("icon_theme", Origin::Settings(settings_path().unwrap().display().to_string()));
+    }
+    if !origins.contains_key("quoting_style") && is_tty(&io::stdout()) {
+        origins.insert("quoting_style", Origin::Terminal);
+    }
+    if terminal_size().is_ok() {
+        origins.insert("width", Origin::Terminal);
+    }
+    let icon_theme = matches.value_of("icon theme")
+        .or_else(|| settings.get("icon_theme").map(|name| name.as_str()))
+        .map(|name| IconTheme::from_name(name).unwrap_or_else(|| {
+            eprintln!("{}: unknown icon theme `{}`", settings_path().unwrap().display(), name);
+            process::exit(2)
+        }))
+        .unwrap_or(IconTheme::Nerd);
+    origins.insert("files", Origin::Builtin(icon_theme.files_source().0.to_string()));
+    origins.insert("folders", Origin::Builtin(icon_theme.folders_source().0.to_string()));
+    origins.insert("file_aliases", Origin::Builtin(FILE_ALIASES.0.to_string()));
+    origins.insert("folder_aliases", Origin::Builtin(FOLDER_ALIASES.0.to_string()));
+    origins.insert("colors", Origin::Builtin(COLORS.0.to_string()));
+    let file_icons = icon_theme.files();
+    let folder_icons = icon_theme.folders();
+    let file_aliases = resolve_aliases(&serde_yaml::from_str(FILE_ALIASES.1).unwrap(), &file_icons)
+        .unwrap_or_else(|e| {
+            eprintln!("{}: {}", FILE_ALIASES.0, e);
+            process::exit(2)
+        });
+    let folder_aliases = resolve_aliases(&serde_yaml::from_str(FOLDER_ALIASES.1).unwrap(), &folder_icons)
+        .unwrap_or_else(|e| {
+            eprintln!("{}: {}", FOLDER_ALIASES.0, e);
+            process::exit(2)
+        });
+    let colors = serde_yaml::from_str(COLORS.1).unwrap();
     let cdir_path = env::current_dir().unwrap();
     let dir = matches.value_of("FILE").unwrap_or_else(|| cdir_path.to_str().unwrap());
     let path = path::PathBuf::from(dir);
-    let width = terminal_size().unwrap().0 as usize;
+    let width = terminal_size().map(|size| size.0 as usize).unwrap_or(80);
     let action = Action {
         directory: path,
         config: Config {
@@ -77,15 +428,51 @@ fn main() {
                 file_aliases,
                 colors,
                 folder_aliases,
-                width,
+                icon_theme,
+                quoting_style,
+                show_inode: matches.is_present("inode"),
+                show_blocks: matches.is_present("size"),
+                show_links: matches.is_present("links"),
+                show_xattr: matches.is_present("long") && cfg!(target_os = "linux"),
+                show_context: matches.is_present("context"),
+                hyperlink,
+                hostname: if hyperlink { hostname() } else { String::new() },
+                current_dir: if hyperlink { env::current_dir().unwrap_or_default() } else { path::PathBuf::new() },
+                check_empty_dirs: !matches.is_present("no empty check"),
+                size_format,
+                time_format,
+                now: SystemTime::now(),
+                columns: ColumnWidths::default(),
             },
             entry_order,
             formatter,
         },
         verbosity,
         tabulator,
+        renderer,
+        report,
+        sort,
+        du: matches.is_present("du"),
+        one_file_system: matches.is_present("one file system"),
+        indicator_style,
+        stream: sort == SortOrder::None && (matches.is_present("one per line") || matches.is_present("long")),
+        parallel_threshold: matches.value_of("parallel threshold").map(|n| n.parse().unwrap()).unwrap_or(DEFAULT_PARALLEL_THRESHOLD),
     };
 
+    if matches.is_present("dump config") {
+        let format = match matches.value_of("dump config") {
+            Some("json") => DumpFormat::Json,
+            _ => DumpFormat::Yaml,
+        };
+        print!("{}", ConfigDump::new(&action, &origins).format(format));
+        return
+    }
+
+    if matches.is_present("show theme") {
+        show_theme(&action);
+        return
+    }
+
     if verbosity == Verbosity::Debug {
         println!("{:?}", action);
 
@@ -0,0 +1,142 @@
+use self::super::colors::{ColorType, RealColor};
+
+const DESCRIPTION : &str = "\
+.SH DESCRIPTION
+List information about the FILEs (the current directory by default), with colors and icons.
+Entries are sorted by name unless another order is requested.
+";
+
+const FILES : &str = "\
+.SH FILES
+.TP
+.I $XDG_CONFIG_HOME/colorls/config.yaml
+User settings, read on every run. A missing file means defaults everywhere.
+Use \\fB\\-\\-check\\-config\\fR to validate it and \\fB\\-\\-dump\\-config\\fR to see the effective result.
+";
+
+const ENVIRONMENT : &str = "\
+.SH ENVIRONMENT
+.TP
+.B XDG_CONFIG_HOME
+Directory holding the \\fIcolorls\\fR settings directory.
+.TP
+.B HOME
+Used as \\fI$HOME/.config\\fR when \\fBXDG_CONFIG_HOME\\fR is unset or empty.
+";
+
+fn escape(text : &str) -> String {
+    text.replace('\\', "\\\\").replace('-', "\\-")
+}
+
+fn format_spec(spec : &str) -> String {
+    let mut out = String::new();
+    let mut rest = spec;
+    while let Some(start) = rest.find('<') {
+        let end = match rest[start..].find('>') {
+            Some(end) => start + end,
+            None => break,
+        };
+        out.push_str(&format_switches(&rest[..start]));
+        out.push_str(&format!("\\fI{}\\fR", escape(&rest[start + 1..end])));
+        rest = &rest[end + 1..];
+    }
+    out.push_str(&format_switches(rest));
+    out
+}
+
+fn format_switches(text : &str) -> String {
+    text.split(", ").map(|part| {
+        let trimmed = part.trim_end_matches(['=', ' ']);
+        if trimmed.is_empty() {
+            escape(part)
+        } else {
+            format!("\\fB{}\\fR{}", escape(trimmed), escape(&part[trimmed.len()..]))
+        }
+    }).collect::<Vec<_>>().join(", ")
+}
+
+// NOTE: Expects clap help rendered with the `{unified}` template and no wrapping: a switch, two or more spaces, then the help.
+fn options(help : &str) -> String {
+    let mut out = String::new();
+    for line in help.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue
+        }
+        let (spec, text) = match line.find("  ") {
+            Some(split) => (&line[..split], line[split..].trim()),
+            None => (line, ""),
+        };
+        out.push_str(&format!(".TP\n{}\n{}\n", format_spec(spec), escape(text)));
+    }
+    out
+}
+
+fn schema() -> String {
+    let colors : Vec<&str> = ColorType::ALL.iter().map(|color| color.name()).collect();
+    let real_colors : Vec<&str> = RealColor::ALL.iter().map(|color| color.name()).collect();
+    format!("\
+.SH CONFIGURATION
+The settings file is a flat YAML map of \\fIkey\\fR: \\fIvalue\\fR pairs.
+.TP
+.B icon_theme
+One of nerd, emoji, ascii or none, overridden by \\fB\\-\\-icon\\-theme\\fR.
+.PP
+Color tables map a color type to a color.
+Color types are: {colors}.
+Colors are: {real_colors}.
+.PP
+Icon tables map a file extension (or folder name) to a glyph, alias tables map an extension to another icon name.
+Aliases may point to other aliases; chains are followed up to their icon and cycles are reported as errors.
+",
+            colors = escape(&colors.join(", ")),
+            real_colors = escape(&real_colors.join(", ")),
+    )
+}
+
+pub fn man_page(version : &str, help : &str) -> String {
+    format!("\
+.TH COLORLS 1 \"\" \"colorls {version}\" \"User Commands\"
+.SH NAME
+colorls \\- list directory contents with colors and icons
+.SH SYNOPSIS
+.B colorls
+[\\fIOPTIONS\\fR] [\\fIFILE\\fR]
+{description}.SH OPTIONS
+{options}{schema}{files}{environment}",
+            version = version,
+            description = DESCRIPTION,
+            options = options(help),
+            schema = schema(),
+            files = FILES,
+            environment = ENVIRONMENT,
+    )
+}
+
+#[cfg(test)]
+mod man_page_tests {
+    use super::*;
+
+    #[test]
+    fn for_simple_case() {
+        assert_eq!(".TP\n\\fB\\-F\\fR, \\fB\\-\\-classify\\fR\nAppends indicator\n", options("    -F, --classify     Appends indicator\n"))
+    }
+
+    #[test]
+    fn for_option_with_value() {
+        assert_eq!(".TP\n\\fB\\-\\-time\\fR \\fIWORD\\fR\nShows WORD [values: mtime, atime]\n", options("        --time <WORD>    Shows WORD [values: mtime, atime]"))
+    }
+
+    #[test]
+    fn for_option_with_optional_value() {
+        assert_eq!(".TP\n\\fB\\-\\-report\\fR=\\fISTYLE\\fR\nPrints a summary\n", options("        --report=<STYLE>    Prints a summary"))
+    }
+
+    #[test]
+    fn for_whole_page() {
+        let page = man_page("1.0", "    -l, --long    Prints using long format\n");
+        assert!(page.starts_with(".TH COLORLS 1 \"\" \"colorls 1.0\""));
+        assert!(page.contains(".SH OPTIONS\n.TP\n\\fB\\-l\\fR, \\fB\\-\\-long\\fR\n"));
+        assert!(page.contains("no_access"))
+    }
+}
@@ -0,0 +1,91 @@
+// NOTE: `mode_t` is u16 on macOS and the BSDs, metadata modes are always u32, so the casts only look unnecessary on Linux.
+#![allow(clippy::unnecessary_cast)]
+
+use libc;
+
+use self::super::colors::ColorType;
+
+const S_IFMT : u32 = libc::S_IFMT as u32;
+const S_IFDIR : u32 = libc::S_IFDIR as u32;
+const S_IFLNK : u32 = libc::S_IFLNK as u32;
+const S_IFIFO : u32 = libc::S_IFIFO as u32;
+const S_IFSOCK : u32 = libc::S_IFSOCK as u32;
+const S_IFCHR : u32 = libc::S_IFCHR as u32;
+const S_IFBLK : u32 = libc::S_IFBLK as u32;
+const S_ISUID : u32 = libc::S_ISUID as u32;
+const S_ISGID : u32 = libc::S_ISGID as u32;
+const S_ISVTX : u32 = libc::S_ISVTX as u32;
+
+fn file_type(mode : u32) -> char {
+    match mode & S_IFMT {
+        S_IFDIR => 'd',
+        S_IFLNK => 'l',
+        S_IFIFO => 'p',
+        S_IFSOCK => 's',
+        S_IFCHR => 'c',
+        S_IFBLK => 'b',
+        _ => '-',
+    }
+}
+
+fn triplet(mode : u32, shift : u32, special : u32, mark : char) -> [char; 3] {
+    let bits = (mode >> shift) & 0o7;
+    let exec = bits & 0o1 != 0;
+    [
+        if bits & 0o4 != 0 { 'r' } else { '-' },
+        if bits & 0o2 != 0 { 'w' } else { '-' },
+        match (mode & special != 0, exec) {
+            (true, true) => mark,
+            (true, false) => mark.to_ascii_uppercase(),
+            (false, true) => 'x',
+            (false, false) => '-',
+        },
+    ]
+}
+
+pub fn mode_string(mode : u32) -> String {
+    let mut out = String::with_capacity(10);
+    out.push(file_type(mode));
+    out.extend(triplet(mode, 6, S_ISUID, 's'));
+    out.extend(triplet(mode, 3, S_ISGID, 's'));
+    out.extend(triplet(mode, 0, S_ISVTX, 't'));
+    out
+}
+
+pub fn mode_color(c : char) -> ColorType {
+    match c {
+        'r' => ColorType::Read,
+        'w' => ColorType::Write,
+        'x' | 's' | 't' => ColorType::Exec,
+        '-' => ColorType::NoAccess,
+        _ => ColorType::NoModifier,
+    }
+}
+
+#[cfg(test)]
+mod mode_string_tests {
+    use super::*;
+
+    #[test]
+    fn for_simple_case() {
+        assert_eq!("-rw-r--r--", mode_string(libc::S_IFREG as u32 | 0o644))
+    }
+
+    #[test]
+    fn for_directory() {
+        assert_eq!("drwxr-xr-x", mode_string(libc::S_IFDIR as u32 | 0o755))
+    }
+
+    #[test]
+    fn for_symlink() {
+        assert_eq!("lrwxrwxrwx", mode_string(libc::S_IFLNK as u32 | 0o777))
+    }
+
+    #[test]
+    fn for_special_bits() {
+        assert_eq!("-rwsr-sr-x", mode_string(libc::S_IFREG as u32 | 0o6755));
+        assert_eq!("-rwSr-Sr--", mode_string(libc::S_IFREG as u32 | 0o6644));
+        assert_eq!("drwxrwxrwt", mode_string(libc::S_IFDIR as u32 | 0o1777));
+        assert_eq!("drwxrwxr-T", mode_string(libc::S_IFDIR as u32 | 0o1774))
+    }
+}
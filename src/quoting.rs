@@ -0,0 +1,225 @@
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+
+#[derive(Hash, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum QuotingStyle {
+    Literal,
+    HideControl,
+    Shell,
+    ShellEscape,
+    C,
+    Escape,
+}
+
+enum Chunk<'a> {
+    Text(&'a str),
+    Control(char),
+    Invalid(u8),
+}
+
+fn chunks(name : &OsStr) -> Vec<Chunk<'_>> {
+    let mut out = vec![];
+    for chunk in name.as_bytes().utf8_chunks() {
+        let valid = chunk.valid();
+        let mut start = 0;
+        for (i, c) in valid.char_indices() {
+            if c.is_control() {
+                if start < i {
+                    out.push(Chunk::Text(&valid[start..i]));
+                }
+                out.push(Chunk::Control(c));
+                start = i + c.len_utf8();
+            }
+        }
+        if start < valid.len() {
+            out.push(Chunk::Text(&valid[start..]));
+        }
+        out.extend(chunk.invalid().iter().map(|b| Chunk::Invalid(*b)));
+    }
+    out
+}
+
+const SHELL_SPECIAL : &str = " \t\n!\"#$&'()*;<=>?[\\]^`{|}~";
+
+fn needs_shell_quotes(name : &OsStr) -> bool {
+    let bytes = name.as_bytes();
+    bytes.is_empty() || bytes.iter().any(|b| SHELL_SPECIAL.as_bytes().contains(b))
+}
+
+fn c_escape(c : char, out : &mut String) {
+    match c {
+        '\x07' => out.push_str("\\a"),
+        '\x08' => out.push_str("\\b"),
+        '\t' => out.push_str("\\t"),
+        '\n' => out.push_str("\\n"),
+        '\x0b' => out.push_str("\\v"),
+        '\x0c' => out.push_str("\\f"),
+        '\r' => out.push_str("\\r"),
+        c => {
+            let mut buf = [0; 4];
+            for b in c.encode_utf8(&mut buf).bytes() {
+                out.push_str(&format!("\\{:03o}", b));
+            }
+        },
+    }
+}
+
+fn hide_control(name : &OsStr) -> String {
+    chunks(name).iter().map(|chunk| match *chunk {
+        Chunk::Text(text) => text.to_string(),
+        Chunk::Control(_) | Chunk::Invalid(_) => "?".to_string(),
+    }).collect()
+}
+
+fn single_quoted(text : &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+fn shell_escape(name : &OsStr) -> String {
+    let chunks = chunks(name);
+    if chunks.iter().all(|chunk| matches!(*chunk, Chunk::Text(_))) {
+        return shell(name)
+    }
+    let mut out = String::new();
+    let mut in_dollar = false;
+    for chunk in chunks {
+        match chunk {
+            Chunk::Text(text) => {
+                if in_dollar {
+                    out.push('\'');
+                    in_dollar = false;
+                }
+                out.push_str(&single_quoted(text));
+            },
+            Chunk::Control(c) => {
+                if !in_dollar {
+                    out.push_str("$'");
+                    in_dollar = true;
+                }
+                c_escape(c, &mut out);
+            },
+            Chunk::Invalid(b) => {
+                if !in_dollar {
+                    out.push_str("$'");
+                    in_dollar = true;
+                }
+                out.push_str(&format!("\\{:03o}", b));
+            },
+        }
+    }
+    if in_dollar {
+        out.push('\'');
+    }
+    out
+}
+
+fn shell(name : &OsStr) -> String {
+    let text = hide_control(name);
+    if needs_shell_quotes(name) {
+        single_quoted(&text)
+    } else {
+        text
+    }
+}
+
+fn backslash_escape(name : &OsStr, escape_spaces : bool) -> String {
+    let mut out = String::new();
+    for chunk in chunks(name) {
+        match chunk {
+            Chunk::Text(text) => for c in text.chars() {
+                match c {
+                    '\\' => out.push_str("\\\\"),
+                    ' ' if escape_spaces => out.push_str("\\ "),
+                    '"' if !escape_spaces => out.push_str("\\\""),
+                    c => out.push(c),
+                }
+            },
+            Chunk::Control(c) => c_escape(c, &mut out),
+            Chunk::Invalid(b) => out.push_str(&format!("\\{:03o}", b)),
+        }
+    }
+    out
+}
+
+impl QuotingStyle {
+    pub fn from_name(name : &str) -> Option<QuotingStyle> {
+        match name {
+            "literal" => Some(QuotingStyle::Literal),
+            "shell" => Some(QuotingStyle::Shell),
+            "shell-escape" => Some(QuotingStyle::ShellEscape),
+            "c" => Some(QuotingStyle::C),
+            "escape" => Some(QuotingStyle::Escape),
+            "hide-control" => Some(QuotingStyle::HideControl),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            QuotingStyle::Literal => "literal",
+            QuotingStyle::HideControl => "hide-control",
+            QuotingStyle::Shell => "shell",
+            QuotingStyle::ShellEscape => "shell-escape",
+            QuotingStyle::C => "c",
+            QuotingStyle::Escape => "escape",
+        }
+    }
+
+    pub fn quote(&self, name : &OsStr) -> String {
+        match *self {
+            QuotingStyle::Literal => name.to_string_lossy().into_owned(),
+            QuotingStyle::HideControl => hide_control(name),
+            QuotingStyle::Shell => shell(name),
+            QuotingStyle::ShellEscape => shell_escape(name),
+            QuotingStyle::C => format!("\"{}\"", backslash_escape(name, false)),
+            QuotingStyle::Escape => backslash_escape(name, true),
+        }
+    }
+}
+
+#[cfg(test)]
+mod quoting_tests {
+    use super::*;
+
+    fn quote(style : QuotingStyle, name : &str) -> String {
+        style.quote(OsStr::new(name))
+    }
+
+    #[test]
+    fn for_plain_name() {
+        for style in &[QuotingStyle::Literal, QuotingStyle::HideControl, QuotingStyle::Shell, QuotingStyle::ShellEscape, QuotingStyle::Escape] {
+            assert_eq!("main.rs", quote(*style, "main.rs"))
+        }
+        assert_eq!("\"main.rs\"", quote(QuotingStyle::C, "main.rs"))
+    }
+
+    #[test]
+    fn for_spaces() {
+        assert_eq!("a b", quote(QuotingStyle::Literal, "a b"));
+        assert_eq!("'a b'", quote(QuotingStyle::Shell, "a b"));
+        assert_eq!("'a b'", quote(QuotingStyle::ShellEscape, "a b"));
+        assert_eq!("\"a b\"", quote(QuotingStyle::C, "a b"));
+        assert_eq!("a\\ b", quote(QuotingStyle::Escape, "a b"))
+    }
+
+    #[test]
+    fn for_control_characters() {
+        assert_eq!("a?b", quote(QuotingStyle::HideControl, "a\nb"));
+        assert_eq!("'a'$'\\n''b'", quote(QuotingStyle::ShellEscape, "a\nb"));
+        assert_eq!("\"a\\tb\"", quote(QuotingStyle::C, "a\tb"));
+        assert_eq!("\\033[31mred", quote(QuotingStyle::Escape, "\x1b[31mred"))
+    }
+
+    #[test]
+    fn for_single_quotes() {
+        assert_eq!("'it'\\''s'", quote(QuotingStyle::Shell, "it's"))
+    }
+
+    #[test]
+    fn for_invalid_utf8() {
+        let name = OsStr::from_bytes(b"a\xffb");
+        assert_eq!("a?b", QuotingStyle::HideControl.quote(name));
+        assert_eq!("a\\377b", QuotingStyle::Escape.quote(name));
+        assert_eq!("'a'$'\\377''b'", QuotingStyle::ShellEscape.quote(name))
+    }
+}
@@ -0,0 +1,171 @@
+use std::fmt;
+
+use termion::color;
+
+use self::super::cell::{Cell, Span};
+use self::super::colors::{ColorType, RealColor};
+use self::super::formatter::{EntryConfig, color_for};
+use self::super::hyperlink::hyperlink;
+
+pub trait Renderer: fmt::Debug {
+    fn render(&self, config : &EntryConfig, cell : &Cell) -> String;
+
+    fn begin(&self) -> String {
+        String::new()
+    }
+
+    fn end(&self) -> String {
+        String::new()
+    }
+
+    fn name(&self) -> &'static str {
+        "custom"
+    }
+}
+
+#[derive(Debug)]
+pub struct AnsiRenderer;
+impl Renderer for AnsiRenderer {
+    fn name(&self) -> &'static str {
+        "ansi"
+    }
+
+    fn render(&self, config : &EntryConfig, cell : &Cell) -> String {
+        let mut out = String::new();
+        for span in &cell.spans {
+            match *span {
+                Span::Text(ref text) => out.push_str(text),
+                Span::Colored(ref color, ref text) => {
+                    out.push_str(&format!("{}{}{}", color::Fg(color_for(config, color)), text, color::Fg(color::Reset)));
+                },
+                Span::Link { ref color, ref text, ref url } => {
+                    out.push_str(&format!("{}{}{}", color::Fg(color_for(config, color)), hyperlink(url, text), color::Fg(color::Reset)));
+                },
+                Span::Icon { ref glyph, .. } => out.push_str(glyph),
+                Span::Padding(width) => out.push_str(&" ".repeat(width)),
+            }
+        }
+        out
+    }
+}
+
+#[derive(Debug)]
+pub struct PlainRenderer;
+impl Renderer for PlainRenderer {
+    fn name(&self) -> &'static str {
+        "plain"
+    }
+
+    fn render(&self, _config : &EntryConfig, cell : &Cell) -> String {
+        let mut out = String::new();
+        for span in &cell.spans {
+            match *span {
+                Span::Text(ref text) | Span::Colored(_, ref text) | Span::Link { ref text, .. } => out.push_str(text),
+                Span::Icon { ref glyph, .. } => out.push_str(glyph),
+                Span::Padding(width) => out.push_str(&" ".repeat(width)),
+            }
+        }
+        out
+    }
+}
+
+fn escape_html(text : &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn css_color(config : &EntryConfig, color : &ColorType) -> &'static str {
+    config.colors.get(color).unwrap_or(&RealColor::Grey).name()
+}
+
+#[derive(Debug)]
+pub struct HtmlRenderer;
+impl Renderer for HtmlRenderer {
+    fn name(&self) -> &'static str {
+        "html"
+    }
+
+    fn render(&self, config : &EntryConfig, cell : &Cell) -> String {
+        let mut out = String::new();
+        for span in &cell.spans {
+            match *span {
+                Span::Text(ref text) => out.push_str(&escape_html(text)),
+                Span::Colored(ref color, ref text) => {
+                    out.push_str(&format!("<span style=\"color: {}\">{}</span>", css_color(config, color), escape_html(text)));
+                },
+                Span::Link { ref color, ref text, ref url } => {
+                    out.push_str(&format!("<a href=\"{}\" style=\"color: {}\">{}</a>", escape_html(url), css_color(config, color), escape_html(text)));
+                },
+                Span::Icon { ref glyph, .. } => out.push_str(&escape_html(glyph)),
+                Span::Padding(width) => out.push_str(&" ".repeat(width)),
+            }
+        }
+        out
+    }
+
+    fn begin(&self) -> String {
+        "<pre class=\"colorls\">\n".to_string()
+    }
+
+    fn end(&self) -> String {
+        "</pre>\n".to_string()
+    }
+}
+
+#[cfg(test)]
+mod renderer_tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn config() -> EntryConfig {
+        let mut colors = HashMap::new();
+        colors.insert(ColorType::Dir, RealColor::Blue);
+        EntryConfig { colors, ..EntryConfig::default() }
+    }
+
+    fn cell() -> Cell {
+        Cell { spans: vec![
+            Span::Text("12 ".to_string()),
+            Span::Icon { glyph: "D".to_string(), width: 1 },
+            Span::Colored(ColorType::Dir, "a<b".to_string()),
+            Span::Padding(2),
+        ] }
+    }
+
+    #[test]
+    fn for_simple_case() {
+        assert_eq!(format!("12 D{}a<b{}  ", color::Fg(color::Blue), color::Fg(color::Reset)), AnsiRenderer.render(&config(), &cell()))
+    }
+
+    #[test]
+    fn for_plain_text() {
+        assert_eq!("12 Da<b  ", PlainRenderer.render(&config(), &cell()))
+    }
+
+    #[test]
+    fn for_html() {
+        assert_eq!("12 D<span style=\"color: blue\">a&lt;b</span>  ", HtmlRenderer.render(&config(), &cell()))
+    }
+
+    #[test]
+    fn when_cell_has_link() {
+        let cell = Cell { spans: vec![Span::Link { color: ColorType::Dir, text: "src".to_string(), url: "file:///a&b".to_string() }] };
+        assert_eq!("src", PlainRenderer.render(&config(), &cell));
+        assert_eq!("<a href=\"file:///a&amp;b\" style=\"color: blue\">src</a>", HtmlRenderer.render(&config(), &cell));
+        assert!(AnsiRenderer.render(&config(), &cell).contains(&hyperlink("file:///a&b", "src")))
+    }
+
+    #[test]
+    fn when_rendered_width_matches_cell() {
+        assert_eq!(cell().width(), PlainRenderer.render(&config(), &cell()).chars().count())
+    }
+}
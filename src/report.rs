@@ -0,0 +1,101 @@
+use self::super::colors::ColorType;
+use self::super::formatter::Entry;
+use self::super::cell::Cell;
+
+#[derive(Hash, Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ReportStyle {
+    Short,
+    Long,
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Report {
+    pub folders: usize,
+    pub files: usize,
+    pub recognized_files: usize,
+    pub unrecognized_files: usize,
+    pub links: usize,
+    pub size: u64,
+}
+
+impl ReportStyle {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            ReportStyle::Short => "short",
+            ReportStyle::Long => "long",
+        }
+    }
+}
+
+impl Report {
+    pub fn add(&mut self, entry : &Entry) {
+        if entry.is_symlink() {
+            self.links += 1;
+        }
+        if entry.is_dir() {
+            self.folders += 1;
+            // NOTE: A directory's own size is not its contents, so it only counts once --du has measured it.
+            if entry.disk_usage.is_some() {
+                self.size += entry.size();
+            }
+            return
+        }
+        self.files += 1;
+        if entry.attr.recognized {
+            self.recognized_files += 1;
+        } else {
+            self.unrecognized_files += 1;
+        }
+        self.size += entry.size();
+    }
+
+    pub fn total(&self) -> usize {
+        self.folders + self.files
+    }
+
+    pub fn format(&self, style : ReportStyle) -> Cell {
+        let body = match style {
+            ReportStyle::Short => format!(
+                "Found {} contents. Folders: {}, Files: {}, Recognized files: {}, Unrecognized files: {}, Symlinks: {}, Total size: {} B",
+                self.total(), self.folders, self.files, self.recognized_files, self.unrecognized_files, self.links, self.size),
+            ReportStyle::Long => format!(
+                "Found {} contents\n\n   Folders            : {}\n   Files              : {}\n   Recognized files   : {}\n   Unrecognized files : {}\n   Symlinks           : {}\n   Total size         : {} B",
+                self.total(), self.folders, self.files, self.recognized_files, self.unrecognized_files, self.links, self.size),
+        };
+        let mut cell = Cell::new();
+        cell.colored(ColorType::Report, body);
+        cell
+    }
+}
+
+#[cfg(test)]
+mod report_tests {
+    use super::*;
+    use std::path;
+    use std::env;
+    use self::super::super::formatter::Attr;
+
+    fn entry(name : &str, color : ColorType, recognized : bool) -> Entry {
+        let mut entry = Entry::new(path::PathBuf::from(name), None);
+        entry.attr = Attr { icon: String::new(), color, recognized };
+        entry
+    }
+
+    #[test]
+    fn for_simple_case() {
+        let mut report = Report::default();
+        report.add(&entry(env::temp_dir().to_str().unwrap(), ColorType::Dir, false));
+        report.add(&entry("/nonexistent/main.rs", ColorType::RecognizedFile, true));
+        report.add(&entry("/nonexistent/build", ColorType::Exec, false));
+        assert_eq!(Report { folders: 1, files: 2, recognized_files: 1, unrecognized_files: 1, links: 0, size: 0 }, report);
+        assert_eq!(3, report.total())
+    }
+
+    #[test]
+    fn when_adding_across_directories() {
+        let mut report = Report::default();
+        report.add(&entry("/nonexistent/a/x.rs", ColorType::RecognizedFile, true));
+        report.add(&entry("/nonexistent/b/x.rs", ColorType::RecognizedFile, true));
+        assert_eq!(2, report.recognized_files)
+    }
+}
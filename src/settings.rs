@@ -0,0 +1,65 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path;
+
+use serde_yaml;
+
+use self::super::formatter::Options;
+
+pub fn settings_path() -> Option<path::PathBuf> {
+    match env::var_os("XDG_CONFIG_HOME") {
+        Some(ref dir) if !dir.is_empty() => Some(path::PathBuf::from(dir)),
+        _ => env::var_os("HOME").map(|home| path::PathBuf::from(home).join(".config")),
+    }.map(|dir| dir.join("colorls").join("config.yaml"))
+}
+
+pub fn load_settings(path : &path::Path) -> Result<Options, String> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Options::new()),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+    if contents.trim().is_empty() {
+        return Ok(Options::new())
+    }
+    serde_yaml::from_str(&contents).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod load_settings_tests {
+    use super::*;
+    use self::super::super::testing::TempDir;
+
+    fn write(dir : &TempDir, contents : &str) -> path::PathBuf {
+        let path = dir.join("config.yaml");
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn for_simple_case() {
+        let dir = TempDir::new("load_settings");
+        let path = write(&dir, "icon_theme: emoji\n");
+        assert_eq!(Some(&"emoji".to_string()), load_settings(&path).unwrap().get("icon_theme"))
+    }
+
+    #[test]
+    fn when_file_is_missing() {
+        assert_eq!(Ok(Options::new()), load_settings(path::Path::new("/nonexistent/colorls.yaml")))
+    }
+
+    #[test]
+    fn when_file_is_empty() {
+        let dir = TempDir::new("load_settings");
+        let path = write(&dir, "\n");
+        assert_eq!(Ok(Options::new()), load_settings(&path))
+    }
+
+    #[test]
+    fn when_file_is_malformed() {
+        let dir = TempDir::new("load_settings");
+        let path = write(&dir, "- a\n- b\n");
+        assert!(load_settings(&path).is_err())
+    }
+}
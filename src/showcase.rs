@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path;
+
+use self::super::cell::Cell;
+use self::super::colors::ColorType;
+use self::super::formatter::{Attr, Entry, EntryConfig, ShortFormat, Options};
+use self::super::icons::IconTheme;
+use self::super::quoting::QuotingStyle;
+use self::super::tabulator::Config;
+use self::super::Action;
+
+const SWATCH : &str = "\u{2588}\u{2588}\u{2588}";
+
+// NOTE: Labels travel through the regular short format as entry names, so they must not contain a slash.
+fn swatch(label : &str, icon : &str, color : ColorType) -> Entry {
+    let mut entry = Entry::new(path::PathBuf::from(label), None);
+    entry.attr = Attr { icon: icon.to_string(), color, recognized: true };
+    entry
+}
+
+fn color_swatches() -> Vec<Entry> {
+    ColorType::ALL.iter().map(|color| swatch(&format!("{} {}", SWATCH, color.name()), "", *color)).collect()
+}
+
+fn aliases_by_target(aliases : &Options) -> HashMap<&str, Vec<&str>> {
+    let mut out : HashMap<&str, Vec<&str>> = HashMap::new();
+    for (alias, target) in aliases {
+        out.entry(target.as_str()).or_default().push(alias.as_str());
+    }
+    for names in out.values_mut() {
+        names.sort();
+    }
+    out
+}
+
+fn truncate(label : String, max_len : usize) -> String {
+    if label.chars().count() <= max_len {
+        return label
+    }
+    let mut out : String = label.chars().take(max_len.saturating_sub(1)).collect();
+    out.push('\u{2026}');
+    out
+}
+
+fn label(name : &str, aliases : &[&str], max_len : usize) -> String {
+    if aliases.is_empty() {
+        return truncate(name.to_string(), max_len)
+    }
+    let mut shown = aliases.len();
+    loop {
+        let rest = aliases.len() - shown;
+        let mut label = format!("{} ({}", name, aliases[..shown].join(", "));
+        if rest > 0 {
+            label.push_str(&format!("{}+{} more", if shown > 0 { ", " } else { "" }, rest));
+        }
+        label.push(')');
+        if shown == 0 {
+            return truncate(label, max_len)
+        }
+        if label.chars().count() <= max_len {
+            return label
+        }
+        shown -= 1;
+    }
+}
+
+fn icon_swatches(icons : &Options, aliases : &Options, color : fn(&str) -> ColorType, max_len : usize) -> Vec<Entry> {
+    let aliases = aliases_by_target(aliases);
+    let mut names : Vec<&String> = icons.keys().collect();
+    names.sort();
+    names.into_iter().map(|name| {
+        let label = label(name, aliases.get(name.as_str()).map(|aliases| &aliases[..]).unwrap_or(&[]), max_len);
+        swatch(&label, &icons[name], color(name))
+    }).collect()
+}
+
+fn file_color(name : &str) -> ColorType {
+    match name {
+        "file" => ColorType::UnrecognizedFile,
+        "exec" => ColorType::Exec,
+        "empty" => ColorType::Empty,
+        _ => ColorType::RecognizedFile,
+    }
+}
+
+fn folder_color(name : &str) -> ColorType {
+    match name {
+        "empty" => ColorType::Empty,
+        _ => ColorType::Dir,
+    }
+}
+
+fn write_section(action : &Action, out : &mut dyn Write, title : &str, entry : EntryConfig, swatches : Vec<Entry>) -> io::Result<()> {
+    let config = Config {
+        entry,
+        max_width: action.config.max_width,
+        formatter: Box::new(ShortFormat),
+        entry_order: action.config.entry_order,
+    };
+    let mut heading = Cell::new();
+    heading.text(format!("{}:", title));
+    writeln!(out, "{}", action.renderer.render(&config.entry, &heading))?;
+    for items in action.tabulator.tabulate(&config, swatches) {
+        for item in items {
+            write!(out, "{}", action.renderer.render(&config.entry, &item))?;
+        }
+        writeln!(out)?;
+    }
+    writeln!(out)
+}
+
+pub fn show_theme(action : &Action) {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let _ = write_theme(action, &mut out);
+}
+
+fn write_theme(action : &Action, out : &mut dyn Write) -> io::Result<()> {
+    let entry = EntryConfig {
+        quoting_style: QuotingStyle::Literal,
+        hyperlink: false,
+        show_inode: false,
+        show_blocks: false,
+        show_links: false,
+        show_context: false,
+        ..action.config.entry.clone()
+    };
+    let colors = EntryConfig { icon_theme: IconTheme::None, ..entry.clone() };
+    write!(out, "{}", action.renderer.begin())?;
+    write_section(action, out, "Colors", colors, color_swatches())?;
+    // NOTE: Capped well below the width, so one long alias list cannot force the whole grid into a single column.
+    let max_len = action.config.max_width / 3;
+    write_section(action, out, "Files", entry.clone(), icon_swatches(&entry.files, &entry.file_aliases, file_color, max_len))?;
+    write_section(action, out, "Folders", entry.clone(), icon_swatches(&entry.folders, &entry.folder_aliases, folder_color, max_len))?;
+    write!(out, "{}", action.renderer.end())
+}
+
+#[cfg(test)]
+mod icon_swatches_tests {
+    use super::*;
+
+    fn options(pairs : &[(&str, &str)]) -> Options {
+        pairs.iter().map(|&(key, value)| (key.to_string(), value.to_string())).collect()
+    }
+
+    fn labels(entries : &[Entry]) -> Vec<String> {
+        entries.iter().map(|entry| entry.path.to_string_lossy().into_owned()).collect()
+    }
+
+    #[test]
+    fn for_simple_case() {
+        let icons = options(&[("rs", "R"), ("file", "F")]);
+        let swatches = icon_swatches(&icons, &Options::new(), file_color, 80);
+        assert_eq!(vec!["file", "rs"], labels(&swatches));
+        assert_eq!(ColorType::UnrecognizedFile, swatches[0].attr.color)
+    }
+
+    #[test]
+    fn when_aliases_resolve_to_icon() {
+        let icons = options(&[("js", "J")]);
+        let aliases = options(&[("mjs", "js"), ("cjs", "js")]);
+        assert_eq!(vec!["js (cjs, mjs)"], labels(&icon_swatches(&icons, &aliases, file_color, 80)))
+    }
+
+    #[test]
+    fn when_aliases_do_not_fit() {
+        assert_eq!("rb (gemfile, +2 more)", label("rb", &["gemfile", "gemspec", "rakefile"], 24));
+        assert_eq!("rb (+3 more)", label("rb", &["gemfile", "gemspec", "rakefile"], 12));
+        assert_eq!("rb (\u{2026}", label("rb", &["gemfile", "gemspec", "rakefile"], 5))
+    }
+
+    #[test]
+    fn for_every_color() {
+        assert_eq!(ColorType::ALL.len(), color_swatches().len())
+    }
+}
+
+#[cfg(test)]
+mod write_theme_tests {
+    use super::*;
+    use self::super::super::{Verbosity, SortOrder, DEFAULT_PARALLEL_THRESHOLD};
+    use self::super::super::indicator::IndicatorStyle;
+    use self::super::super::render::PlainRenderer;
+    use self::super::super::tabulator::{EntryOrder, OptimalTabulator};
+
+    fn action(entry : EntryConfig) -> Action {
+        Action {
+            verbosity: Verbosity::Quiet,
+            directory: path::PathBuf::from("."),
+            config: Config { entry, max_width: 80, formatter: Box::new(ShortFormat), entry_order: EntryOrder::Vertical },
+            tabulator: Box::new(OptimalTabulator),
+            renderer: Box::new(PlainRenderer),
+            report: None,
+            sort: SortOrder::None,
+            du: false,
+            one_file_system: false,
+            indicator_style: IndicatorStyle::None,
+            parallel_threshold: DEFAULT_PARALLEL_THRESHOLD,
+            stream: false,
+        }
+    }
+
+    fn output(action : &Action) -> String {
+        let mut out = Vec::new();
+        write_theme(action, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn for_simple_case() {
+        let mut entry = EntryConfig { icon_theme: IconTheme::None, ..EntryConfig::default() };
+        entry.files.insert("rs".to_string(), "R".to_string());
+        let text = output(&action(entry));
+        assert!(text.contains("Colors:\n"));
+        assert!(text.lines().any(|line| line.trim_end() == "Rrs"), "{}", text)
+    }
+
+    #[test]
+    fn when_alias_list_is_long() {
+        let mut entry = EntryConfig { icon_theme: IconTheme::None, ..EntryConfig::default() };
+        for name in ["c", "go", "js", "py", "rb", "rs", "sh", "ts"] {
+            entry.files.insert(name.to_string(), name.to_uppercase());
+        }
+        for index in 0..30 {
+            entry.file_aliases.insert(format!("javascript{}", index), "js".to_string());
+        }
+        let mut action = action(entry);
+        action.config.max_width = 40;
+        let text = output(&action);
+        assert!(text.lines().all(|line| line.chars().count() <= 40), "{}", text);
+        let rows = text.lines().skip_while(|line| *line != "Files:").skip(1).take_while(|line| !line.is_empty()).count();
+        assert_eq!(2, rows, "{}", text)
+    }
+}
@@ -0,0 +1,247 @@
+use self::super::colors::ColorType;
+
+const PREFIXES : [&str; 8] = ["K", "M", "G", "T", "P", "E", "Z", "Y"];
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SizeUnits {
+    Bytes,
+    Iec,
+    Si,
+    Block { factor: u64, suffix: String },
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SizeFormat {
+    pub units: SizeUnits,
+    pub precision: Option<usize>,
+}
+
+impl Default for SizeFormat {
+    fn default() -> SizeFormat {
+        SizeFormat { units: SizeUnits::Bytes, precision: None }
+    }
+}
+
+// NOTE: Rounds up, like ls does, so a listed size is never smaller than the real one.
+fn ceil_to(value : f64, precision : usize) -> f64 {
+    let scale = 10f64.powi(precision as i32);
+    (value * scale - 1e-9).ceil() / scale
+}
+
+// NOTE: The unit and precision are chosen on the rounded value, 1023.9K rounds up to 1.0M and 9.99K to 10K.
+fn scaled(size : u64, base : f64, precision : Option<usize>) -> String {
+    let mut value = size as f64;
+    let mut index = None;
+    for i in 0..PREFIXES.len() {
+        if value < base {
+            break
+        }
+        value /= base;
+        index = Some(i);
+    }
+    let mut index = match index {
+        Some(index) => index,
+        None => return format!("{}", size),
+    };
+    loop {
+        let precision = precision.unwrap_or(if ceil_to(value, 1) < 10.0 { 1 } else { 0 });
+        let rounded = ceil_to(value, precision);
+        if rounded >= base && index + 1 < PREFIXES.len() {
+            value /= base;
+            index += 1;
+            continue
+        }
+        return format!("{value:.precision$}{prefix}", value = rounded, precision = precision, prefix = PREFIXES[index])
+    }
+}
+
+impl SizeUnits {
+    pub fn name(&self) -> String {
+        match *self {
+            SizeUnits::Bytes => "1".to_string(),
+            SizeUnits::Iec => "human-readable".to_string(),
+            SizeUnits::Si => "si".to_string(),
+            SizeUnits::Block { ref suffix, .. } if !suffix.is_empty() => suffix.clone(),
+            SizeUnits::Block { factor, .. } => factor.to_string(),
+        }
+    }
+}
+
+impl SizeFormat {
+    pub fn format(&self, size : u64) -> String {
+        match self.units {
+            SizeUnits::Bytes => format!("{}", size),
+            SizeUnits::Iec => scaled(size, 1024.0, self.precision),
+            SizeUnits::Si => scaled(size, 1000.0, self.precision),
+            SizeUnits::Block { factor, ref suffix } => {
+                let precision = self.precision.unwrap_or(0);
+                format!("{value:.precision$}{suffix}",
+                        value = ceil_to(size as f64 / factor as f64, precision),
+                        precision = precision,
+                        suffix = suffix)
+            },
+        }
+    }
+
+    pub fn widest(&self) -> usize {
+        match self.units {
+            SizeUnits::Iec => self.format(1023 * 1024).len(),
+            SizeUnits::Si => self.format(999 * 1000).len(),
+            SizeUnits::Bytes | SizeUnits::Block { .. } => 0,
+        }
+    }
+
+    pub fn color(&self, size : u64) -> ColorType {
+        let base = if self.units == SizeUnits::Si { 1000 } else { 1024 };
+        if size < base {
+            ColorType::SizeB
+        } else if size < base * base {
+            ColorType::SizeKb
+        } else if size < base * base * base {
+            ColorType::SizeMb
+        } else {
+            ColorType::SizeGb
+        }
+    }
+}
+
+pub fn parse_block_size(value : &str) -> Result<SizeUnits, String> {
+    match value {
+        "human-readable" => return Ok(SizeUnits::Iec),
+        "si" => return Ok(SizeUnits::Si),
+        _ => {},
+    }
+    let split = value.find(|c : char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let multiplier = if number.is_empty() {
+        1
+    } else {
+        number.parse::<u64>().map_err(|_| format!("invalid block size: {}", value))?
+    };
+    let (prefix, base) = match unit.len() {
+        0 => return match multiplier {
+            0 => Err(format!("invalid block size: {}", value)),
+            1 => Ok(SizeUnits::Bytes),
+            factor => Ok(SizeUnits::Block { factor, suffix: String::new() }),
+        },
+        1 => (unit, 1024u64),
+        2 if unit.ends_with('B') => (&unit[..1], 1000),
+        3 if unit.ends_with("iB") => (&unit[..1], 1024),
+        _ => return Err(format!("invalid block size: {}", value)),
+    };
+    let exponent = match PREFIXES.iter().position(|p| *p == prefix.to_uppercase()) {
+        Some(i) if i < 6 => i as u32 + 1,
+        _ => return Err(format!("invalid block size: {}", value)),
+    };
+    match multiplier {
+        0 => Err(format!("invalid block size: {}", value)),
+        _ if number.is_empty() => Ok(SizeUnits::Block { factor: base.pow(exponent), suffix: unit.to_string() }),
+        _ => Ok(SizeUnits::Block { factor: multiplier * base.pow(exponent), suffix: String::new() }),
+    }
+}
+
+#[cfg(test)]
+mod size_format_tests {
+    use super::*;
+
+    fn format(units : SizeUnits, precision : Option<usize>, size : u64) -> String {
+        SizeFormat { units, precision }.format(size)
+    }
+
+    #[test]
+    fn for_bytes() {
+        assert_eq!("1536", format(SizeUnits::Bytes, None, 1536))
+    }
+
+    #[test]
+    fn for_iec() {
+        assert_eq!("1023", format(SizeUnits::Iec, None, 1023));
+        assert_eq!("1.5K", format(SizeUnits::Iec, None, 1536));
+        assert_eq!("12K", format(SizeUnits::Iec, None, 12 * 1024));
+        assert_eq!("1.1K", format(SizeUnits::Iec, None, 1025));
+        assert_eq!("3.0G", format(SizeUnits::Iec, None, 3 * 1024 * 1024 * 1024))
+    }
+
+    #[test]
+    fn for_si() {
+        assert_eq!("1.5K", format(SizeUnits::Si, None, 1500));
+        assert_eq!("2.0M", format(SizeUnits::Si, None, 2_000_000))
+    }
+
+    #[test]
+    fn for_fixed_precision() {
+        assert_eq!("1.50K", format(SizeUnits::Iec, Some(2), 1536));
+        assert_eq!("12.00K", format(SizeUnits::Iec, Some(2), 12 * 1024))
+    }
+
+    #[test]
+    fn when_rounding_reaches_next_unit() {
+        assert_eq!("1.0M", format(SizeUnits::Iec, None, 1048575));
+        assert_eq!("1.0M", format(SizeUnits::Si, None, 999_999));
+        assert_eq!("1.00M", format(SizeUnits::Iec, Some(2), 1048575))
+    }
+
+    #[test]
+    fn when_rounding_reaches_ten() {
+        assert_eq!("10K", format(SizeUnits::Iec, None, 10230));
+        assert_eq!("9.9K", format(SizeUnits::Iec, None, 10137))
+    }
+
+    #[test]
+    fn for_widest() {
+        assert_eq!(5, SizeFormat { units: SizeUnits::Iec, precision: None }.widest());
+        assert_eq!(4, SizeFormat { units: SizeUnits::Si, precision: None }.widest());
+        assert_eq!(8, SizeFormat { units: SizeUnits::Iec, precision: Some(2) }.widest());
+        assert_eq!(0, SizeFormat::default().widest())
+    }
+
+    #[test]
+    fn for_block_size() {
+        assert_eq!("2M", format(parse_block_size("M").unwrap(), None, 1024 * 1024 + 1));
+        assert_eq!("3", format(parse_block_size("512").unwrap(), None, 1536))
+    }
+
+    #[test]
+    fn for_magnitude_colors() {
+        let format = SizeFormat::default();
+        assert_eq!(ColorType::SizeB, format.color(1023));
+        assert_eq!(ColorType::SizeKb, format.color(1024));
+        assert_eq!(ColorType::SizeMb, format.color(5 * 1024 * 1024));
+        assert_eq!(ColorType::SizeGb, format.color(5 * 1024 * 1024 * 1024))
+    }
+}
+
+#[cfg(test)]
+mod parse_block_size_tests {
+    use super::*;
+
+    #[test]
+    fn for_simple_case() {
+        assert_eq!(Ok(SizeUnits::Block { factor: 1024, suffix: "K".to_string() }), parse_block_size("K"))
+    }
+
+    #[test]
+    fn for_si_and_iec_suffixes() {
+        assert_eq!(Ok(SizeUnits::Block { factor: 1_000_000, suffix: "MB".to_string() }), parse_block_size("MB"));
+        assert_eq!(Ok(SizeUnits::Block { factor: 1024 * 1024, suffix: "MiB".to_string() }), parse_block_size("MiB"))
+    }
+
+    #[test]
+    fn for_multiplied_unit() {
+        assert_eq!(Ok(SizeUnits::Block { factor: 4096, suffix: String::new() }), parse_block_size("4K"))
+    }
+
+    #[test]
+    fn when_invalid() {
+        assert!(parse_block_size("Q").is_err());
+        assert!(parse_block_size("0").is_err());
+        assert!(parse_block_size("KX").is_err())
+    }
+
+    #[test]
+    fn for_scaled_units() {
+        assert_eq!(Ok(SizeUnits::Iec), parse_block_size("human-readable"));
+        assert_eq!(Ok(SizeUnits::Si), parse_block_size("si"));
+        assert_eq!(Ok(SizeUnits::Bytes), parse_block_size("1"))
+    }
+}
@@ -1,15 +1,16 @@
-use std::cmp::max;
+use std::cmp::{max, min};
 use num_iter::range_step;
 
 use std::fmt;
 
 use self::super::formatter::{Formatter,Entry,EntryConfig};
+use self::super::cell::Cell;
 
 #[derive(Debug)]
 pub struct Config {
     pub entry: EntryConfig,
     pub max_width: usize,
-    pub formatter: Box<Formatter>,
+    pub formatter: Box<dyn Formatter>,
     pub entry_order : EntryOrder,
 }
 
@@ -21,8 +22,17 @@ pub enum EntryOrder {
     Vertical
 }
 
-fn break_lines_with<T : Clone>(order : EntryOrder, names : &Vec<T>, n_cols : usize) -> Rows<T> {
-    let n_rows = (names.len() + n_cols - 1) / n_cols;
+impl EntryOrder {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            EntryOrder::Horizontal => "horizontal",
+            EntryOrder::Vertical => "vertical",
+        }
+    }
+}
+
+fn break_lines_with<T : Clone>(order : EntryOrder, names : &[T], n_cols : usize) -> Rows<T> {
+    let n_rows = names.len().div_ceil(n_cols);
     let mut rows = vec![Vec::with_capacity(n_cols); n_rows];
     for (i, out) in names.iter().enumerate() {
         let pos = match order {
@@ -39,12 +49,12 @@ mod horizontal_line_breaker_tests {
     use super::*;
     #[test]
     fn for_simple_case() {
-        assert_eq!(vec![vec![1,2], vec![3,4]], break_lines_with(EntryOrder::Horizontal, &vec![1,2,3,4], 2))
+        assert_eq!(vec![vec![1,2], vec![3,4]], break_lines_with(EntryOrder::Horizontal, &[1,2,3,4], 2))
     }
 
     #[test]
     fn when_only_one_row() {
-        assert_eq!(vec![vec![1,2,3]], break_lines_with(EntryOrder::Horizontal, &vec![1,2,3], 10))
+        assert_eq!(vec![vec![1,2,3]], break_lines_with(EntryOrder::Horizontal, &[1,2,3], 10))
     }
 }
 
@@ -53,29 +63,35 @@ mod vertical_line_breaker_tests {
     use super::*;
     #[test]
     fn for_simple_case() {
-        assert_eq!(vec![vec![1,3], vec![2,4]], break_lines_with(EntryOrder::Vertical, &vec![1,2,3,4], 2))
+        assert_eq!(vec![vec![1,3], vec![2,4]], break_lines_with(EntryOrder::Vertical, &[1,2,3,4], 2))
     }
 
     #[test]
     fn when_only_one_row() {
-        assert_eq!(vec![vec![1,2,3]], break_lines_with(EntryOrder::Vertical, &vec![1,2,3], 10))
+        assert_eq!(vec![vec![1,2,3]], break_lines_with(EntryOrder::Vertical, &[1,2,3], 10))
     }
 }
 
-type Output = Rows<String>;
+type Output = Rows<Cell>;
 pub trait Tabulator: fmt::Debug {
-    fn tabulate(&self, &Config, Vec<Entry>) -> Output;
+    fn tabulate(&self, config : &Config, names : Vec<Entry>) -> Output;
+
+    fn name(&self) -> &'static str {
+        "custom"
+    }
 }
 
 type ColumnSetup = Vec<usize>;
 
+#[cfg(test)]
 fn is_valid(out : &ColumnSetup, max_width : usize) -> bool {
     let mut width = 0;
     for c in out { width += *c }
-    return width < max_width
+    width < max_width
 }
 
 // NOTE: Assumes out has same-sized rows
+#[cfg(test)]
 fn column_setup(out : Vec<Vec<usize>>) -> ColumnSetup {
     if out.is_empty() {
         return vec![]
@@ -94,84 +110,115 @@ mod is_valid_tests {
     use super::*;
     #[test]
     fn for_simple_case() {
-        assert_eq!(false, is_valid(&column_setup(vec![vec![1,2], vec![2,1]]), 2))
+        assert!(!is_valid(&column_setup(vec![vec![1,2], vec![2,1]]), 2))
     }
 
     #[test]
     fn when_total_col_width_exceeds_max() {
-        assert_eq!(false, is_valid(&column_setup(vec![vec![1,3], vec![3,1]]), 5))
+        assert!(!is_valid(&column_setup(vec![vec![1,3], vec![3,1]]), 5))
     }
 
     #[test]
     fn when_fits() {
-        assert_eq!(true, is_valid(&column_setup(vec![vec![1,2], vec![1,1]]), 4))
+        assert!(is_valid(&column_setup(vec![vec![1,2], vec![1,1]]), 4))
     }
 }
 
-fn predict_column_setup(config: &Config, names : &Vec<Entry>, n_cols : usize) -> ColumnSetup {
-    column_setup(break_lines_with(config.entry_order, &names.iter().map(|e| config.formatter.predict(e)).collect(), n_cols))
+fn predict_widths(config : &Config, names : &[Entry]) -> Vec<usize> {
+    names.iter().map(|e| config.formatter.predict(&config.entry, e)).collect()
+}
+
+fn used_columns(order : EntryOrder, n : usize, n_cols : usize) -> usize {
+    match order {
+        EntryOrder::Horizontal => min(n, n_cols),
+        EntryOrder::Vertical if n == 0 => 0,
+        EntryOrder::Vertical => n.div_ceil(n.div_ceil(n_cols)),
+    }
+}
+
+fn column_width(order : EntryOrder, widths : &[usize], n_cols : usize, column : usize) -> usize {
+    let widest = match order {
+        EntryOrder::Horizontal => widths.iter().skip(column).step_by(n_cols).max(),
+        EntryOrder::Vertical => {
+            let n_rows = widths.len().div_ceil(n_cols);
+            widths.iter().skip(column * n_rows).take(n_rows).max()
+        },
+    };
+    widest.cloned().unwrap_or(0)
+}
+
+fn column_setup_for(order : EntryOrder, widths : &[usize], n_cols : usize) -> ColumnSetup {
+    (0..used_columns(order, widths.len(), n_cols)).map(|column| column_width(order, widths, n_cols, column)).collect()
 }
 
-fn is_valid_as_rows(config: &Config, names : &Vec<Entry>, n_cols : usize) -> Option<ColumnSetup> {
-    let setup = predict_column_setup(config, names, n_cols);
-    if is_valid(&setup, config.max_width) {
-        Some(setup)
-    } else { None }
+fn fits(order : EntryOrder, widths : &[usize], n_cols : usize, max_width : usize) -> bool {
+    let mut width = 0;
+    for column in 0..used_columns(order, widths.len(), n_cols) {
+        width += column_width(order, widths, n_cols, column);
+        if width >= max_width {
+            return false
+        }
+    }
+    true
 }
 
-fn format_as_rows(config : &Config, names : &Vec<Entry>, col_widths : ColumnSetup) -> Output {
+fn format_as_rows(config : &Config, names : &[Entry], col_widths : ColumnSetup) -> Output {
     let n_cols = col_widths.len();
     let rows = break_lines_with(config.entry_order, names, n_cols);
-    let entry_configs : Vec<EntryConfig> = col_widths.iter().map(|width| EntryConfig{width: *width, ..config.entry.clone()}).collect();
     let mut out : Output = rows.iter().map(|r| Vec::with_capacity(r.len())).collect();
     for (y, row) in rows.iter().enumerate() {
         for (x, item) in row.iter().enumerate() {
-            out[y].push(config.formatter.format(&entry_configs[x], item));
+            out[y].push(config.formatter.format(&config.entry, item, col_widths[x]));
         }
     }
     out
 }
 
-fn max_width(config : &Config, names : &Vec<Entry>) -> usize {
-    let mut width = 0;
-    for l in names {
-        let cwidth = config.formatter.predict(l);
-        if cwidth > width {
-            width = cwidth;
+const MIN_FORMAT_ENTRY_LENGTH : usize = 5;
+
+// NOTE: Cannot use usize, due to negative range.
+fn column_range(widths : &[usize], max_width : usize) -> (i64, i64) {
+    let width = widths.iter().cloned().max().unwrap_or(0);
+    let min_cols = max((max_width / (width + 1)) as i64, 1);
+    let max_cols = (max_width / MIN_FORMAT_ENTRY_LENGTH) as i64;
+    (min_cols, max_cols)
+}
+
+fn planning_column_setup(order : EntryOrder, widths : &[usize], max_width : usize) -> ColumnSetup {
+    let (min_cols, max_cols) = column_range(widths, max_width);
+    for n_cols in range_step(max_cols, min_cols, -1) {
+        if fits(order, widths, n_cols as usize, max_width) {
+            return column_setup_for(order, widths, n_cols as usize)
         }
     }
-    width
+    column_setup_for(order, widths, 1)
 }
 
-const MIN_FORMAT_ENTRY_LENGTH : usize = 5;
-
 #[derive(Debug)]
 pub struct PlanningTabulator;
 impl Tabulator for PlanningTabulator {
+    fn name(&self) -> &'static str {
+        "planning"
+    }
+
     fn tabulate(&self, config : &Config, names : Vec<Entry>) -> Output {
-        let width = max_width(config, &names);
-        let min_cols = max((config.max_width / (width + 1)) as i64, 1);
-        let max_cols = (config.max_width / MIN_FORMAT_ENTRY_LENGTH) as i64;
-        for n_cols in range_step(max_cols, min_cols, -1) {
-            if let Some(column_setup) = is_valid_as_rows(config, &names, n_cols as usize) {
-                return format_as_rows(config, &names, column_setup)
-            }
-        }
-        format_as_rows(config, &names, predict_column_setup(config, &names, 1))
+        let widths = predict_widths(config, &names);
+        format_as_rows(config, &names, planning_column_setup(config.entry_order, &widths, config.max_width))
     }
 }
 
-fn binsearch<T, F>(range : &Vec<T>, pred : F) -> Option<usize>
+fn binsearch<T, F>(range : &[T], pred : F) -> Option<usize>
     where F: Fn(&T) -> bool {
-    let mut slice = &range[..];
+    let mut slice = range;
     let mut index = 0;
     let mut counter = range.len();
     loop {
         if slice.is_empty() {
             return None
         }
-        counter -= 1;
+        // NOTE: Checked before decrementing, so a single candidate does not trip it.
         assert!(counter > 0);
+        counter -= 1;
 
         let mid = slice.len() / 2;
         let mid_value = &slice[mid];
@@ -196,23 +243,29 @@ mod binsearch_tests {
     use super::*;
     #[test]
     fn for_simple_case() {
-        assert_eq!(Some(1), binsearch(&vec![0,1,2], |i| *i >= 1))
+        assert_eq!(Some(1), binsearch(&[0,1,2], |i| *i >= 1))
     }
 
     #[test]
     fn when_nothing_matches() {
-        assert_eq!(None, binsearch(&vec![0,1,2], |i| *i >= 3))
+        assert_eq!(None, binsearch(&[0,1,2], |i| *i >= 3))
     }
 
     #[test]
     fn when_array_is_small() {
-        assert_eq!(Some(1), binsearch(&vec![0,1], |i| *i >= 1));
-        assert_eq!(Some(0), binsearch(&vec![0,1], |i| *i >= 0))
+        assert_eq!(Some(1), binsearch(&[0,1], |i| *i >= 1));
+        assert_eq!(Some(0), binsearch(&[0,1], |i| *i >= 0))
+    }
+
+    #[test]
+    fn when_array_has_one_element() {
+        assert_eq!(Some(0), binsearch(&[5], |_| true));
+        assert_eq!(None, binsearch(&[5], |_| false))
     }
 
     #[test]
     fn when_array_is_big() {
-        assert_eq!(Some(11), binsearch(&vec![0,1,2,3,4,5,6,7,8,9,10,11,12,13,14], |i| *i >= 11));
+        assert_eq!(Some(11), binsearch(&[0,1,2,3,4,5,6,7,8,9,10,11,12,13,14], |i| *i >= 11));
     }
 
     #[test]
@@ -222,30 +275,237 @@ mod binsearch_tests {
 }
 
 
+fn binsearch_column_setup(order : EntryOrder, widths : &[usize], max_width : usize) -> ColumnSetup {
+    let (min_cols, max_cols) = column_range(widths, max_width);
+    let range : Vec<i64> = range_step(max_cols, min_cols, -1).collect();
+    let n_cols = if let Some(idx) = binsearch(&range, |n_cols| fits(order, widths, *n_cols as usize, max_width)) {
+        range[idx]
+    } else {
+        1
+    };
+    column_setup_for(order, widths, n_cols as usize)
+}
+
 #[derive(Debug)]
 pub struct BinsearchTabulator;
 impl Tabulator for BinsearchTabulator {
+    fn name(&self) -> &'static str {
+        "binsearch"
+    }
+
     fn tabulate(&self, config : &Config, names : Vec<Entry>) -> Output {
-        let width = max_width(config, &names);
-        // NOTE: Cannot use usize, due to negative range.
-        let min_cols = max((config.max_width / (width + 1)) as i64, 1);
-        let max_cols = (config.max_width / MIN_FORMAT_ENTRY_LENGTH) as i64;
-        let range : Vec<i64> = range_step(max_cols, min_cols, -1).collect();
-        let n_cols = if let Some(idx) = binsearch(&range, |n_cols| is_valid_as_rows(config, &names, *n_cols as usize).is_some()) {
-            range[idx]
-        } else {
-            1
-        };
-        format_as_rows(config, &names, predict_column_setup(config, &names, n_cols as usize))
+        let widths = predict_widths(config, &names);
+        format_as_rows(config, &names, binsearch_column_setup(config.entry_order, &widths, config.max_width))
     }
 }
 
+fn naive_column_setup(order : EntryOrder, widths : &[usize], max_width : usize) -> ColumnSetup {
+    let width = widths.iter().cloned().max().unwrap_or(0) + 2;
+    column_setup_for(order, widths, max(max_width / width, 1))
+}
+
 #[derive(Debug)]
 pub struct NaiveTabulator;
 impl Tabulator for NaiveTabulator {
+    fn name(&self) -> &'static str {
+        "naive"
+    }
+
+    fn tabulate(&self, config : &Config, names : Vec<Entry>) -> Output {
+        let widths = predict_widths(config, &names);
+        format_as_rows(config, &names, naive_column_setup(config.entry_order, &widths, config.max_width))
+    }
+}
+
+#[derive(Debug)]
+pub struct SingleColumnTabulator;
+impl Tabulator for SingleColumnTabulator {
+    fn name(&self) -> &'static str {
+        "single-column"
+    }
+
+    // NOTE: Nothing follows the only column, so names are left unpadded, exactly as a streamed listing prints them.
+    fn tabulate(&self, config : &Config, names : Vec<Entry>) -> Output {
+        names.iter().map(|entry| vec![config.formatter.format(&config.entry, entry, 0)]).collect()
+    }
+}
+
+#[cfg(test)]
+mod column_setup_tests {
+    use super::*;
+
+    fn reference(order : EntryOrder, widths : &[usize], n_cols : usize) -> ColumnSetup {
+        column_setup(break_lines_with(order, widths, n_cols))
+    }
+
+    fn reference_planning(order : EntryOrder, widths : &[usize], max_width : usize) -> ColumnSetup {
+        let (min_cols, max_cols) = column_range(widths, max_width);
+        range_step(max_cols, min_cols, -1)
+            .map(|n_cols| reference(order, widths, n_cols as usize))
+            .find(|setup| is_valid(setup, max_width))
+            .unwrap_or_else(|| reference(order, widths, 1))
+    }
+
+    fn reference_binsearch(order : EntryOrder, widths : &[usize], max_width : usize) -> ColumnSetup {
+        let (min_cols, max_cols) = column_range(widths, max_width);
+        let range : Vec<i64> = range_step(max_cols, min_cols, -1).collect();
+        let n_cols = binsearch(&range, |n_cols| is_valid(&reference(order, widths, *n_cols as usize), max_width)).map(|idx| range[idx]).unwrap_or(1);
+        reference(order, widths, n_cols as usize)
+    }
+
+    fn samples() -> Vec<Vec<usize>> {
+        let mut samples = vec![vec![3], vec![3, 2, 2, 9, 9, 8, 3], vec![40, 2, 2, 2]];
+        for n in &[2, 5, 17, 64, 200] {
+            samples.push((0..*n).map(|i| (i * 7919) % 23 + 2).collect());
+            samples.push((0..*n).map(|i| (i * 104729) % 61 + 3).collect());
+        }
+        samples
+    }
+
+    #[test]
+    fn for_simple_case() {
+        assert_eq!(vec![3, 9, 3], column_setup_for(EntryOrder::Vertical, &[3, 2, 2, 9, 9, 8, 3], 3));
+        assert_eq!(vec![9, 9, 8], column_setup_for(EntryOrder::Horizontal, &[3, 2, 2, 9, 9, 8, 3], 3))
+    }
+
+    #[test]
+    fn when_compared_with_materialized_rows() {
+        for widths in samples() {
+            for order in &[EntryOrder::Vertical, EntryOrder::Horizontal] {
+                for n_cols in 1..widths.len() + 2 {
+                    let setup = reference(*order, &widths, n_cols);
+                    assert_eq!(setup, column_setup_for(*order, &widths, n_cols), "{:?} {:?} {}", order, widths, n_cols);
+                    for max_width in &[10, 40, 80, 133] {
+                        assert_eq!(is_valid(&setup, *max_width), fits(*order, &widths, n_cols, *max_width), "{:?} {:?} {} {}", order, widths, n_cols, max_width)
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn when_tabulators_are_compared_with_previous_layouts() {
+        for widths in samples() {
+            for order in &[EntryOrder::Vertical, EntryOrder::Horizontal] {
+                for max_width in &[10, 40, 80, 133] {
+                    assert_eq!(reference_planning(*order, &widths, *max_width), planning_column_setup(*order, &widths, *max_width));
+                    assert_eq!(reference_binsearch(*order, &widths, *max_width), binsearch_column_setup(*order, &widths, *max_width))
+                }
+            }
+        }
+    }
+}
+
+// NOTE: Same idea as GNU ls: one pass over the widths keeps the column widths of every candidate count,
+// so fitting is checked for all counts at once instead of assuming it is monotonic.
+fn optimal_column_setup(order : EntryOrder, widths : &[usize], max_width : usize) -> ColumnSetup {
+    let n = widths.len();
+    let narrowest = max(widths.iter().cloned().min().unwrap_or(1), 1);
+    let max_cols = max(min(n, max_width / narrowest), 1);
+    let mut candidates : Vec<(ColumnSetup, usize, bool)> = (1..max_cols + 1).map(|n_cols| (vec![0; n_cols], 0, true)).collect();
+    for (i, width) in widths.iter().enumerate() {
+        for (c, candidate) in candidates.iter_mut().enumerate() {
+            let (ref mut columns, ref mut line_width, ref mut valid) = *candidate;
+            // NOTE: A single column is the fallback, so it is measured in full even when it overflows.
+            if !*valid && c > 0 {
+                continue
+            }
+            let n_cols = c + 1;
+            let column = match order {
+                EntryOrder::Horizontal => i % n_cols,
+                EntryOrder::Vertical => i / n.div_ceil(n_cols),
+            };
+            if *width > columns[column] {
+                *line_width += width - columns[column];
+                columns[column] = *width;
+                *valid = *line_width < max_width;
+            }
+        }
+    }
+    let best = candidates.iter().rposition(|&(_, _, valid)| valid).unwrap_or(0);
+    let mut columns = candidates.swap_remove(best).0;
+    // NOTE: Vertical order can leave trailing columns empty (5 entries in 4 columns use 3), drop them.
+    let used = columns.iter().rposition(|width| *width > 0).map(|last| last + 1).unwrap_or(1);
+    columns.truncate(used);
+    columns
+}
+
+#[cfg(test)]
+mod optimal_column_setup_tests {
+    use super::*;
+    use std::path;
+    use self::super::super::formatter::{Attr, ShortFormat};
+    use self::super::super::colors::ColorType;
+    use self::super::super::icons::IconTheme;
+
+    fn exhaustive(order : EntryOrder, widths : &[usize], max_width : usize) -> ColumnSetup {
+        (1..widths.len() + 1).rev()
+            .map(|n_cols| column_setup(break_lines_with(order, widths, n_cols)))
+            .find(|setup| is_valid(setup, max_width))
+            .unwrap_or_else(|| column_setup(break_lines_with(order, widths, 1)))
+    }
+
+    #[test]
+    fn for_simple_case() {
+        assert_eq!(vec![3, 3, 3], optimal_column_setup(EntryOrder::Vertical, &[3, 3, 3], 20))
+    }
+
+    #[test]
+    fn when_fitting_is_not_monotonic() {
+        let widths = [3, 2, 2, 9, 9, 8, 3];
+        assert!(!is_valid(&column_setup(break_lines_with(EntryOrder::Vertical, &widths, 2)), 18));
+        assert_eq!(vec![3, 9, 3], optimal_column_setup(EntryOrder::Vertical, &widths, 18))
+    }
+
+    #[test]
+    fn when_nothing_fits() {
+        assert_eq!(vec![30], optimal_column_setup(EntryOrder::Horizontal, &[30, 10], 20))
+    }
+
+    #[test]
+    fn for_horizontal_order() {
+        assert_eq!(vec![4, 7], optimal_column_setup(EntryOrder::Horizontal, &[4, 7, 2, 5, 3], 12))
+    }
+
+    #[test]
+    fn when_names_are_short() {
+        let names : Vec<Entry> = (0..60).map(|i| {
+            let mut entry = Entry::new(path::PathBuf::from(if i < 56 { "a" } else { "bb" }), None);
+            entry.attr = Attr { icon: String::new(), color: ColorType::UnrecognizedFile, recognized: false };
+            entry
+        }).collect();
+        let entry = EntryConfig { icon_theme: IconTheme::None, ..EntryConfig::default() };
+        let config = Config { entry, max_width: 80, formatter: Box::new(ShortFormat), entry_order: EntryOrder::Vertical };
+        let widths = predict_widths(&config, &names);
+        let reference = exhaustive(EntryOrder::Vertical, &widths, 80);
+        let rows = OptimalTabulator.tabulate(&config, names).len();
+        assert_eq!(break_lines_with(EntryOrder::Vertical, &widths, reference.len()).len(), rows);
+        assert!(rows < 60_usize.div_ceil(80 / MIN_FORMAT_ENTRY_LENGTH))
+    }
+
+    #[test]
+    fn when_compared_with_exhaustive_search() {
+        let widths : Vec<usize> = (0..200).map(|i| (i * 7919) % 23 + 2).collect();
+        for order in &[EntryOrder::Vertical, EntryOrder::Horizontal] {
+            for max_width in &[10, 40, 80, 133, 200] {
+                for n in &[1, 2, 5, 17, 64, 200] {
+                    let widths = &widths[..*n];
+                    assert_eq!(exhaustive(*order, widths, *max_width), optimal_column_setup(*order, widths, *max_width), "{:?} {} {}", order, max_width, n)
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct OptimalTabulator;
+impl Tabulator for OptimalTabulator {
+    fn name(&self) -> &'static str {
+        "optimal"
+    }
+
     fn tabulate(&self, config : &Config, names : Vec<Entry>) -> Output {
-        let width = max_width(config, &names) + 2;
-        let rows = max(config.max_width / width, 1);
-        format_as_rows(config, &names, predict_column_setup(config, &names, rows as usize))
+        let widths = predict_widths(config, &names);
+        format_as_rows(config, &names, optimal_column_setup(config.entry_order, &widths, config.max_width))
     }
 }
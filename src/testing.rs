@@ -0,0 +1,35 @@
+use std::env;
+use std::fs;
+use std::path;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static COUNTER : AtomicUsize = AtomicUsize::new(0);
+
+// NOTE: Unique per test (tests run in parallel threads of one process) and removed on drop.
+pub struct TempDir {
+    path: path::PathBuf,
+}
+
+impl TempDir {
+    pub fn new(name : &str) -> TempDir {
+        let unique = format!("colorls_{}_{}_{}", name, process::id(), COUNTER.fetch_add(1, Ordering::SeqCst));
+        let path = env::temp_dir().join(unique);
+        fs::create_dir_all(&path).unwrap();
+        TempDir { path }
+    }
+
+    pub fn path(&self) -> &path::Path {
+        &self.path
+    }
+
+    pub fn join(&self, name : &str) -> path::PathBuf {
+        self.path.join(name)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,244 @@
+use std::ffi::CString;
+use std::fs;
+use std::mem;
+use std::os::unix::fs::MetadataExt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use libc;
+
+use self::super::colors::ColorType;
+
+const HOUR : u64 = 60 * 60;
+const DAY : u64 = 24 * HOUR;
+const HALF_YEAR : u64 = 365 * DAY / 2;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TimeField {
+    Modified,
+    Accessed,
+    Changed,
+    Birth,
+}
+
+impl TimeField {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            TimeField::Modified => "mtime",
+            TimeField::Accessed => "atime",
+            TimeField::Changed => "ctime",
+            TimeField::Birth => "birth",
+        }
+    }
+
+    pub fn of(&self, metadata : &fs::Metadata) -> Option<SystemTime> {
+        match *self {
+            TimeField::Modified => metadata.modified().ok(),
+            TimeField::Accessed => metadata.accessed().ok(),
+            TimeField::Changed => Some(from_unix(metadata.ctime(), metadata.ctime_nsec())),
+            // NOTE: std reads the birth time with statx where the kernel and file system support it.
+            TimeField::Birth => metadata.created().ok(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum TimeStyle {
+    Locale,
+    Iso,
+    LongIso,
+    FullIso,
+    Relative,
+    Format(String),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TimeFormat {
+    pub field: TimeField,
+    pub style: TimeStyle,
+}
+
+impl TimeStyle {
+    pub fn name(&self) -> String {
+        match *self {
+            TimeStyle::Locale => "locale".to_string(),
+            TimeStyle::Iso => "iso".to_string(),
+            TimeStyle::LongIso => "long-iso".to_string(),
+            TimeStyle::FullIso => "full-iso".to_string(),
+            TimeStyle::Relative => "relative".to_string(),
+            TimeStyle::Format(ref format) => format!("+{}", format),
+        }
+    }
+}
+
+impl Default for TimeFormat {
+    fn default() -> TimeFormat {
+        TimeFormat { field: TimeField::Modified, style: TimeStyle::Locale }
+    }
+}
+
+fn from_unix(secs : i64, nsecs : i64) -> SystemTime {
+    if secs >= 0 {
+        UNIX_EPOCH + Duration::new(secs as u64, nsecs as u32)
+    } else {
+        UNIX_EPOCH - Duration::new(secs.unsigned_abs(), 0) + Duration::new(0, nsecs as u32)
+    }
+}
+
+// NOTE: Negative when time is in the future.
+fn age(time : SystemTime, now : SystemTime) -> i64 {
+    match now.duration_since(time) {
+        Ok(age) => age.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    }
+}
+
+fn strftime(format : &str, time : SystemTime) -> String {
+    let (secs, nsecs) = match time.duration_since(UNIX_EPOCH) {
+        Ok(d) => (d.as_secs() as i64, d.subsec_nanos()),
+        Err(e) => (-(e.duration().as_secs() as i64), 0),
+    };
+    // NOTE: %N (nanoseconds) is a GNU date extension strftime does not know about.
+    let format = match CString::new(format.replace("%N", &format!("{:09}", nsecs))) {
+        Ok(format) => format,
+        Err(_) => return String::new(),
+    };
+    let secs = secs as libc::time_t;
+    let mut buf = vec![0u8; 256];
+    let len = unsafe {
+        let mut tm : libc::tm = mem::zeroed();
+        libc::localtime_r(&secs, &mut tm);
+        libc::strftime(buf.as_mut_ptr() as *mut libc::c_char, buf.len(), format.as_ptr(), &tm)
+    };
+    String::from_utf8_lossy(&buf[..len]).into_owned()
+}
+
+fn plural(n : i64, unit : &str) -> String {
+    if n == 1 {
+        format!("{} {} ago", n, unit)
+    } else {
+        format!("{} {}s ago", n, unit)
+    }
+}
+
+fn relative(age : i64) -> String {
+    let secs = age as u64;
+    if age < 0 {
+        "in the future".to_string()
+    } else if secs < 60 {
+        "just now".to_string()
+    } else if secs < HOUR {
+        plural(age / 60, "minute")
+    } else if secs < DAY {
+        plural(age / HOUR as i64, "hour")
+    } else if secs < 7 * DAY {
+        plural(age / DAY as i64, "day")
+    } else if secs < 30 * DAY {
+        plural(age / (7 * DAY) as i64, "week")
+    } else if secs < 365 * DAY {
+        plural(age / (30 * DAY) as i64, "month")
+    } else {
+        plural(age / (365 * DAY) as i64, "year")
+    }
+}
+
+impl TimeFormat {
+    pub fn format(&self, time : SystemTime, now : SystemTime) -> String {
+        let age = age(time, now);
+        let recent = age >= 0 && (age as u64) < HALF_YEAR;
+        match self.style {
+            TimeStyle::Locale if recent => strftime("%b %e %H:%M", time),
+            TimeStyle::Locale => strftime("%b %e  %Y", time),
+            TimeStyle::Iso if recent => strftime("%m-%d %H:%M", time),
+            TimeStyle::Iso => strftime("%Y-%m-%d ", time),
+            TimeStyle::LongIso => strftime("%Y-%m-%d %H:%M", time),
+            TimeStyle::FullIso => strftime("%Y-%m-%d %H:%M:%S.%N %z", time),
+            TimeStyle::Relative => relative(age),
+            TimeStyle::Format(ref format) => strftime(format, time),
+        }
+    }
+
+    pub fn color(&self, time : SystemTime, now : SystemTime) -> ColorType {
+        let age = age(time, now);
+        if age < HOUR as i64 {
+            ColorType::HourOld
+        } else if age < DAY as i64 {
+            ColorType::DayOld
+        } else {
+            ColorType::NoModifier
+        }
+    }
+}
+
+pub fn parse_time_style(value : &str) -> Result<TimeStyle, String> {
+    match value {
+        "locale" => Ok(TimeStyle::Locale),
+        "iso" => Ok(TimeStyle::Iso),
+        "long-iso" => Ok(TimeStyle::LongIso),
+        "full-iso" => Ok(TimeStyle::FullIso),
+        "relative" => Ok(TimeStyle::Relative),
+        _ if value.starts_with('+') => Ok(TimeStyle::Format(value[1..].to_string())),
+        _ => Err(format!("invalid time style: {} (expected locale, iso, long-iso, full-iso, relative or +FORMAT)", value)),
+    }
+}
+
+#[cfg(test)]
+mod time_format_tests {
+    use super::*;
+
+    fn ago(secs : u64) -> (SystemTime, SystemTime) {
+        let now = UNIX_EPOCH + Duration::from_secs(1_500_000_000);
+        (now - Duration::from_secs(secs), now)
+    }
+
+    fn relative(secs : u64) -> String {
+        let (time, now) = ago(secs);
+        TimeFormat { field: TimeField::Modified, style: TimeStyle::Relative }.format(time, now)
+    }
+
+    #[test]
+    fn for_relative_style() {
+        assert_eq!("just now", relative(5));
+        assert_eq!("1 minute ago", relative(60));
+        assert_eq!("3 hours ago", relative(3 * HOUR + 5));
+        assert_eq!("2 days ago", relative(2 * DAY));
+        assert_eq!("5 years ago", relative(5 * 365 * DAY))
+    }
+
+    #[test]
+    fn for_custom_format() {
+        let (time, now) = ago(0);
+        let format = TimeFormat { field: TimeField::Modified, style: TimeStyle::Format("%s.%N".to_string()) };
+        assert_eq!("1500000000.000000000", format.format(time, now))
+    }
+
+    #[test]
+    fn for_age_colors() {
+        let format = TimeFormat::default();
+        let (time, now) = ago(HOUR - 1);
+        assert_eq!(ColorType::HourOld, format.color(time, now));
+        let (time, now) = ago(HOUR);
+        assert_eq!(ColorType::DayOld, format.color(time, now));
+        let (time, now) = ago(2 * DAY);
+        assert_eq!(ColorType::NoModifier, format.color(time, now))
+    }
+}
+
+#[cfg(test)]
+mod parse_time_style_tests {
+    use super::*;
+
+    #[test]
+    fn for_simple_case() {
+        assert_eq!(Ok(TimeStyle::LongIso), parse_time_style("long-iso"))
+    }
+
+    #[test]
+    fn for_custom_format() {
+        assert_eq!(Ok(TimeStyle::Format("%H:%M".to_string())), parse_time_style("+%H:%M"))
+    }
+
+    #[test]
+    fn when_invalid() {
+        assert!(parse_time_style("posix").is_err())
+    }
+}
@@ -0,0 +1,119 @@
+#[cfg(target_os = "linux")]
+use std::ffi::CString;
+#[cfg(target_os = "linux")]
+use std::os::unix::ffi::OsStrExt;
+use std::path;
+#[cfg(target_os = "linux")]
+use std::ptr;
+
+#[cfg(target_os = "linux")]
+use libc;
+
+const SELINUX : &str = "security.selinux";
+
+#[cfg(target_os = "linux")]
+fn c_path(path : &path::Path) -> Option<CString> {
+    CString::new(path.as_os_str().as_bytes()).ok()
+}
+
+// NOTE: Works on the entry itself (l* variants), symlinks are not followed.
+#[cfg(target_os = "linux")]
+pub fn list(path : &path::Path) -> Vec<String> {
+    let path = match c_path(path) {
+        Some(path) => path,
+        None => return vec![],
+    };
+    let size = unsafe { libc::llistxattr(path.as_ptr(), ptr::null_mut(), 0) };
+    if size <= 0 {
+        return vec![]
+    }
+    let mut buf = vec![0u8; size as usize];
+    let size = unsafe { libc::llistxattr(path.as_ptr(), buf.as_mut_ptr() as *mut libc::c_char, buf.len()) };
+    if size <= 0 {
+        return vec![]
+    }
+    buf.truncate(size as usize);
+    buf.split(|b| *b == 0).filter(|name| !name.is_empty()).map(|name| String::from_utf8_lossy(name).into_owned()).collect()
+}
+
+#[cfg(target_os = "linux")]
+pub fn get(path : &path::Path, name : &str) -> Option<Vec<u8>> {
+    let path = c_path(path)?;
+    let name = CString::new(name).ok()?;
+    let size = unsafe { libc::lgetxattr(path.as_ptr(), name.as_ptr(), ptr::null_mut(), 0) };
+    if size < 0 {
+        return None
+    }
+    let mut buf = vec![0u8; size as usize];
+    let size = unsafe { libc::lgetxattr(path.as_ptr(), name.as_ptr(), buf.as_mut_ptr() as *mut libc::c_void, buf.len()) };
+    if size < 0 {
+        return None
+    }
+    buf.truncate(size as usize);
+    Some(buf)
+}
+
+// NOTE: libc only has the l*xattr calls for Linux, elsewhere entries show no suffix and no context.
+#[cfg(not(target_os = "linux"))]
+pub fn list(_path : &path::Path) -> Vec<String> {
+    vec![]
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn get(_path : &path::Path, _name : &str) -> Option<Vec<u8>> {
+    None
+}
+
+fn suffix_for(names : &[String]) -> &'static str {
+    if names.iter().any(|name| name.starts_with("system.posix_acl_")) {
+        "+"
+    } else if names.iter().any(|name| name != SELINUX) {
+        "@"
+    } else {
+        ""
+    }
+}
+
+// NOTE: `+` wins over `@` like in ls; the SELinux label alone does not count, every file has one there.
+pub fn mode_suffix(path : &path::Path) -> &'static str {
+    suffix_for(&list(path))
+}
+
+pub fn selinux_context(path : &path::Path) -> Option<String> {
+    get(path, SELINUX).map(|label| {
+        let label = match label.iter().position(|b| *b == 0) {
+            Some(end) => &label[..end],
+            None => &label[..],
+        };
+        String::from_utf8_lossy(label).into_owned()
+    })
+}
+
+#[cfg(test)]
+mod suffix_tests {
+    use super::*;
+
+    fn names(names : &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn for_simple_case() {
+        assert_eq!("", suffix_for(&names(&[])))
+    }
+
+    #[test]
+    fn for_acl() {
+        assert_eq!("+", suffix_for(&names(&["user.comment", "system.posix_acl_access"])))
+    }
+
+    #[test]
+    fn for_extended_attributes() {
+        assert_eq!("@", suffix_for(&names(&["user.comment"])))
+    }
+
+    #[test]
+    fn when_only_selinux_label_is_set() {
+        assert_eq!("", suffix_for(&names(&["security.selinux"])))
+    }
+}